@@ -0,0 +1,85 @@
+//! Announcing focus sessions to the team in a Slack channel or a Matrix room, for teams doing
+//! their pomodoros together. Unlike the Slack status, these are messages everyone in the channel
+//! sees.
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Announce {
+    pub slack: Option<SlackChannel>,
+    pub matrix: Option<MatrixRoom>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlackChannel {
+    /// Bot or user token with the `chat:write` scope
+    pub token: String,
+    /// Channel ID or name
+    pub channel: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixRoom {
+    /// e.g. `https://matrix.org`
+    pub homeserver: String,
+    pub access_token: String,
+    /// e.g. `!abcdefg:matrix.org`
+    pub room_id: String,
+}
+
+impl Announce {
+    /// Say a session started, ending at `until` if it has an end. Waits for the messages to be
+    /// sent, failures are ignored.
+    pub fn start(&self, until: Option<DateTime<Local>>) {
+        let message = match until {
+            Some(until) => format!("🍅 Focus block until {}", until.format("%H:%M")),
+            None => "🍅 Focus block started".to_string(),
+        };
+        self.send(&message);
+    }
+
+    /// Say the session is over.
+    pub fn end(&self) {
+        self.send("Focus block over");
+    }
+
+    fn send(&self, message: &str) {
+        if let Some(slack) = &self.slack {
+            let _ = ureq::post("https://slack.com/api/chat.postMessage")
+                .set("Authorization", &format!("Bearer {}", slack.token))
+                .timeout(TIMEOUT)
+                .send_json(json!({ "channel": slack.channel, "text": message }));
+        }
+        if let Some(matrix) = &self.matrix {
+            // The transaction ID only has to be new for each message
+            let url = format!(
+                "{}/_matrix/client/v3/rooms/{}/send/m.room.message/pomors{}",
+                matrix.homeserver.trim_end_matches('/'),
+                percent_encode(&matrix.room_id),
+                Utc::now().timestamp_millis()
+            );
+            let _ = ureq::put(&url)
+                .set("Authorization", &format!("Bearer {}", matrix.access_token))
+                .timeout(TIMEOUT)
+                .send_json(json!({ "msgtype": "m.text", "body": message }));
+        }
+    }
+}
+
+/// `text` safe to put in a URL path.
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,1467 @@
+//! The state of a running timer, and everything it does on transitions.
+
+use chrono::{DateTime, Local, Utc};
+use crossterm::event::{self as terminal, KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{layout::Rect, style::Color};
+use std::{
+    error::Error,
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    audio::{self, AudioSink},
+    background, calendar,
+    cli::{Period, TimerArgs},
+    clock::{Clock, SystemClock},
+    config::{save_config_value, Config, PaneLayout, VisualBell, ALARM_SOUND},
+    control, discord, dnd,
+    event::{self, Event},
+    format, git, history,
+    history::{History, PeriodKind},
+    hooks, http, i3, input,
+    io::{
+        pomors_dir, stop_on_signals, SESSION_FILE, TASKS_FILE, TIME_ENTRIES_FILE,
+        TODOIST_CACHE_FILE,
+    },
+    keymap::{self, Action},
+    lock, mqtt, notify, push, session, status_file,
+    task::{Remote, StatefulList, Task},
+    theme::Theme,
+    time_tracking,
+    timer::{self, next_time_of_day, AppState, Timer, Transition},
+    todoist, todotxt,
+    ui::Tab,
+    webhook, websocket,
+};
+#[cfg(unix)]
+use crate::{gnome_pomodoro, mpris};
+
+/// How long toasts stay up, the last part of it faded.
+pub const TOAST_LENGTH: Duration = Duration::from_secs(2);
+pub const TOAST_FADE: Duration = Duration::from_millis(500);
+
+pub const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
+pub const VISUAL_BELL_LENGTH: Duration = Duration::from_secs(1);
+
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often the timer is checked on, at most.
+pub const TICK_RATE: Duration = Duration::from_millis(250);
+
+pub struct App {
+    pub config: Config,
+    /// `None` when there is no sound device or alarm sound to play
+    pub audio: Option<Box<dyn AudioSink>>,
+    pub tasks: StatefulList,
+    pub timer: Timer,
+    /// When the visual bell stops flashing
+    pub flash_until: Option<Instant>,
+    /// Set by a stop command, for the main loop to quit on
+    pub stopped: bool,
+    /// Whether a pomodoro was quit in the middle of
+    pub interrupted: bool,
+    /// When the session should end, with the periods fitted around it
+    pub until: Option<DateTime<Local>>,
+    /// Print events to stdout as JSON lines, when running without the TUI
+    pub events: bool,
+    /// The todo.txt file the task list is synced with
+    pub todo_txt: Option<PathBuf>,
+    /// Where the session is saved to be resumed, and when it last was
+    pub session_path: Option<PathBuf>,
+    pub session_saved_at: Instant,
+    /// Handed out to whatever wants to control the app
+    pub commands: Sender<control::Command>,
+    /// Everything to react to, in the order it happened
+    pub queue: (Sender<Event>, Receiver<Event>),
+    /// Where commands are run and services called, away from the UI
+    pub background: background::Worker,
+    /// What's reported to other processes asking through the control socket
+    pub status: control::SharedStatus,
+    pub tasks_status: control::SharedTasks,
+    /// Where to serve the HTTP API, if anywhere
+    pub listen: Option<SocketAddr>,
+    /// Clients of the HTTP API listening for events
+    pub websocket_clients: websocket::Clients,
+    /// Where the status is shown on Discord, if it is
+    pub presence: Option<discord::Presence>,
+    /// Where the status is published over MQTT, if anywhere
+    pub mqtt: Option<mqtt::Publisher>,
+    /// Holds off desktop notifications while working, if turned on
+    pub do_not_disturb: Option<dnd::DoNotDisturb>,
+    /// The i3 or sway session told about the status, if any
+    pub i3: Option<i3::Ipc>,
+    /// Where the status is kept for other tools to read, if anywhere
+    pub status_file: Option<status_file::StatusFile>,
+    /// Where pomodoros are sent as time entries, if anywhere
+    pub time_tracker: Option<time_tracking::Tracker>,
+    pub show_help: bool,
+    /// Asking whether to quit in the middle of a pomodoro
+    pub confirm_quit: bool,
+    /// The name of the task being typed in, while adding one
+    pub new_task: Option<String>,
+    /// Messages from the quotes file, and the one shown this pomodoro
+    pub quotes: Vec<String>,
+    pub quote: Option<usize>,
+    /// Whether the break takeover screen was dismissed for the current break
+    pub break_dismissed: bool,
+    /// Feedback for the last action, and until when to show it
+    pub toast: Option<(String, Instant)>,
+    pub theme: Theme,
+    pub tab: Tab,
+    pub history: History,
+    /// Wall clock time of when the period started, for the history
+    pub period_started_at: DateTime<Utc>,
+    /// The git checkout the pomodoro started in
+    pub git: Option<git::Checkout>,
+    pub session_started_at: DateTime<Utc>,
+    /// No sounds until unmuted
+    pub muted: bool,
+    /// Where changes to the config, like the layout, are saved
+    pub config_path: Option<PathBuf>,
+    /// Where the task list was last drawn, if it's on screen, for handling clicks
+    pub task_list_area: Option<Rect>,
+    /// When and which task was last clicked, to spot double clicks
+    pub last_click: Option<(Instant, usize)>,
+    /// Always use the single line view
+    pub compact: bool,
+    /// When the timer was last checked on. Real time, as that's what waiting for events takes.
+    pub last_tick: Instant,
+    /// Where the time comes from
+    pub clock: Box<dyn Clock>,
+}
+
+impl App {
+    pub fn new(
+        task_list: Vec<String>,
+        config: Config,
+        audio: Option<Box<dyn AudioSink>>,
+        history: History,
+        clock: Box<dyn Clock>,
+    ) -> App {
+        let theme = config.theme;
+        let muted = !config.sound;
+        let (commands, command_receiver) = control::channel();
+        let queue = event::channel();
+        event::forward(command_receiver, queue.0.clone());
+        App {
+            timer: Timer::new(&config, clock.monotonic()),
+            config,
+            audio,
+            flash_until: None,
+            stopped: false,
+            interrupted: false,
+            until: None,
+            events: false,
+            todo_txt: None,
+            session_path: None,
+            session_saved_at: clock.monotonic(),
+            commands,
+            background: background::Worker::start(queue.0.clone()),
+            queue,
+            status: control::SharedStatus::default(),
+            tasks_status: control::SharedTasks::default(),
+            listen: None,
+            websocket_clients: websocket::Clients::default(),
+            presence: None,
+            mqtt: None,
+            do_not_disturb: None,
+            i3: None,
+            status_file: None,
+            time_tracker: None,
+            show_help: false,
+            confirm_quit: false,
+            new_task: None,
+            quotes: Vec::new(),
+            quote: None,
+            break_dismissed: false,
+            toast: None,
+            theme: theme.theme(),
+            tab: Tab::Timer,
+            history,
+            period_started_at: clock.now(),
+            git: None,
+            session_started_at: clock.now(),
+            muted,
+            config_path: None,
+            task_list_area: None,
+            last_click: None,
+            compact: false,
+            clock,
+            last_tick: Instant::now(),
+            tasks: StatefulList::with_items(
+                task_list.iter().map(|name| Task::parse(name)).collect(),
+            ),
+        }
+    }
+
+    pub fn period_length(&self) -> Duration {
+        self.timer.period_length(self.clock.monotonic())
+    }
+
+    /// Length of the current break, or the next one while working.
+    pub fn break_length(&self) -> Duration {
+        self.timer.break_length()
+    }
+
+    /// Whether the current break, or the next one while working, is the long one at the end of
+    /// the cycle.
+    pub fn is_long_break(&self) -> bool {
+        self.timer.is_long_break()
+    }
+
+    pub fn on_tick(&mut self) {
+        self.check_estimate();
+
+        for event in self.timer.tick(self.clock.monotonic()) {
+            match event {
+                timer::Event::SessionOver => self.end_session(),
+                timer::Event::PeriodEnded => self.next_period(),
+                timer::Event::LastMinute => self.print_event("last_minute"),
+                timer::Event::LongBreakDue => {}
+            }
+        }
+        if self.clock.monotonic() - self.session_saved_at >= SESSION_SAVE_INTERVAL {
+            // Not worth interrupting the session for, it's saved again soon
+            let _ = self.save_session();
+        }
+        self.publish_status();
+    }
+
+    /// Pick up changes made to the todo.txt file: new tasks are added and tasks marked done or
+    /// not done there are marked here too.
+    pub fn sync_todo_txt(&mut self) {
+        let Some(path) = &self.todo_txt else {
+            return;
+        };
+        let Ok(items) = todotxt::read(path) else {
+            return;
+        };
+
+        for item in items {
+            let task = Task::parse(&item.text);
+            match self.tasks.items.iter_mut().find(|t| t.name == task.name) {
+                Some(existing) => {
+                    existing.is_complete = item.done;
+                    existing.priority = task.priority;
+                }
+                None => {
+                    let mut task = task;
+                    task.is_complete = item.done;
+                    self.tasks.push(task);
+                }
+            }
+        }
+        if self.tasks.state.selected().is_none() {
+            self.tasks.next(self.clock.now());
+        }
+    }
+
+    pub fn save_session(&mut self) -> io::Result<()> {
+        let Some(path) = &self.session_path else {
+            return Ok(());
+        };
+        let session = session::Session {
+            saved_at: self.clock.now(),
+            tasks: self.tasks.items.clone(),
+            selected: self
+                .tasks
+                .state
+                .selected()
+                .map(|selected| self.tasks.order[selected]),
+            state: self.timer.state,
+            pomodoros_in_cycle: self.timer.pomodoros_in_cycle,
+            elapsed: self.elapsed(),
+        };
+        self.session_saved_at = self.clock.monotonic();
+        session.save(path)
+    }
+
+    /// Pick up where `session` left off, in the middle of the same period.
+    pub fn restore_session(&mut self, mut session: session::Session) {
+        // The time the task was being worked on ended when the session was last saved
+        for task in &mut session.tasks {
+            task.stop_work(session.saved_at);
+        }
+        self.tasks = StatefulList::with_items(session.tasks);
+        self.tasks.tracking = session.state == AppState::Working;
+        if let Some(selected) = session.selected {
+            self.tasks.select(selected, self.clock.now());
+        }
+        self.timer.restore(
+            session.state,
+            session.pomodoros_in_cycle,
+            session.elapsed,
+            self.clock.monotonic(),
+        );
+        // The time before quitting is already in the history, as an interrupted pomodoro
+        self.period_started_at = self.clock.now();
+    }
+
+    /// Close the work period of the selected task, then keep the session to resume if a pomodoro
+    /// was cut short, otherwise it's done with. Returns the summary of the session and whether it
+    /// was kept, to show on quitting.
+    pub fn close_session(&mut self) -> String {
+        if let Some(task) = self.tasks.get_selected_mut() {
+            task.stop_work(self.clock.now());
+        }
+        let mut report = self.summary();
+        if self.interrupted {
+            let saved = self.save_session();
+            if let (Some(path), Ok(())) = (&self.session_path, &saved) {
+                report += &format!(
+                    "\nSaved the session to {}, to pick up with --resume",
+                    path.display()
+                );
+            } else if let Err(e) = saved {
+                report += &format!("\nCouldn't save the session: {e}");
+            }
+        } else if let Some(path) = &self.session_path {
+            let _ = fs::remove_file(path);
+        }
+        report
+    }
+
+    /// Update the status reported through the control socket.
+    pub fn publish_status(&mut self) {
+        let status = control::Status {
+            state: self.timer.state,
+            long_break: self.timer.state == AppState::TakingABreak && self.is_long_break(),
+            paused: self.timer.is_paused(),
+            remaining_secs: self.remaining().as_secs(),
+            length_secs: self.period_length().as_secs(),
+            task: self.get_current_task_name().cloned(),
+        };
+        if let Some(presence) = &self.presence {
+            presence.update(status.clone());
+        }
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.update(status.clone());
+        }
+        if let Some(i3) = &self.i3 {
+            i3.update(status.clone());
+        }
+        if let Some(status_file) = &mut self.status_file {
+            // Tried again on the next tick
+            let _ = status_file.update(Some(&status));
+        }
+        if let Ok(mut shared) = self.status.lock() {
+            *shared = Some(status);
+        }
+        if let Ok(mut shared) = self.tasks_status.lock() {
+            let selected = self.tasks.state.selected();
+            *shared = (self.tasks.order.iter().enumerate())
+                .map(|(i, &item)| {
+                    let task = &self.tasks.items[item];
+                    control::TaskStatus {
+                        name: task.name.clone(),
+                        done: task.is_complete,
+                        selected: selected == Some(i),
+                        tracked_secs: task.tracked_duration(self.clock.now()).num_seconds(),
+                        estimate: task.estimate,
+                    }
+                })
+                .collect();
+        }
+    }
+
+    /// React to the next thing that happened.
+    pub fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Input(terminal::Event::Key(key)) => self.handle_key(key.code),
+            Event::Input(terminal::Event::Mouse(mouse)) if !self.is_break_takeover() => {
+                self.handle_mouse(mouse)
+            }
+            Event::Input(_) => {}
+            Event::Tick => self.on_tick(),
+            Event::Command(command) => self.handle_command(command),
+            Event::FileChanged(path) if self.todo_txt.as_ref() == Some(&path) => {
+                self.sync_todo_txt()
+            }
+            Event::FileChanged(_) => {}
+            Event::Message(message) => self.show_toast(message),
+        }
+    }
+
+    /// Handle events until stopped, when running without a UI.
+    pub fn run(&mut self) {
+        while !self.stopped {
+            let Some(event) = self.next_event(TICK_RATE) else {
+                return;
+            };
+            self.handle_event(event);
+        }
+    }
+
+    /// Wait for the next event, or a `Tick` once `timeout` has passed since the last one. `None`
+    /// when there can be no more events.
+    pub fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        let tick_at = self.last_tick + timeout;
+        // Ticks are due even when events keep coming
+        let event = match tick_at.checked_duration_since(Instant::now()) {
+            Some(wait) => self.queue.1.recv_timeout(wait),
+            None => Err(RecvTimeoutError::Timeout),
+        };
+        match event {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => {
+                self.last_tick = Instant::now();
+                Some(Event::Tick)
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// How long the UI can wait before checking on the timer: until the time left turns over to
+    /// the next second, or `TICK_RATE` while a toast or the visual bell is showing.
+    pub fn tick_timeout(&self) -> Duration {
+        let remaining = self.remaining();
+        if remaining.is_zero() || self.is_animating() {
+            return TICK_RATE;
+        }
+        // Just past the turn, so the new second is what's shown
+        Duration::from_nanos(remaining.subsec_nanos().into()) + Duration::from_millis(1)
+    }
+
+    /// Whether something on screen changes by itself faster than once a second.
+    pub fn is_animating(&self) -> bool {
+        self.current_toast().is_some() || self.is_flashing() || self.is_breathing()
+    }
+
+    /// Whether the breathing animation of a running break is on screen.
+    pub fn is_breathing(&self) -> bool {
+        self.timer.state == AppState::TakingABreak
+            && !self.timer.is_paused()
+            && self.tab == Tab::Timer
+            && self.config.layout != PaneLayout::TasksOnly
+            && !self.compact
+            && !self.is_break_takeover()
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) {
+        if self.is_break_takeover() {
+            // Any key goes back to the timer
+            self.break_dismissed = true;
+            return;
+        }
+        if let Some(name) = &mut self.new_task {
+            match key {
+                KeyCode::Char(c) => return name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                    return;
+                }
+                _ => {}
+            }
+        }
+        let Some(action) = keymap::action(self.mode(), key) else {
+            return;
+        };
+        match action {
+            Action::Quit if self.timer.state == AppState::Working => self.confirm_quit = true,
+            Action::Confirm => {
+                self.interrupt();
+                self.stopped = true;
+            }
+            Action::Cancel => {
+                self.confirm_quit = false;
+                self.new_task = None;
+            }
+            Action::Quit => self.stopped = true,
+            Action::NextTask => self.tasks.next(self.clock.now()),
+            Action::PreviousTask => self.tasks.previous(self.clock.now()),
+            Action::ToggleTask => self.toggle_current_task(),
+            Action::BackspaceTask => self.backspace_task(),
+            Action::AddTask => match self.new_task.take() {
+                None => self.new_task = Some(String::new()),
+                Some(name) if name.trim().is_empty() => {}
+                Some(name) => self.add_task(&name),
+            },
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::NextTab => self.tab = self.tab.next(),
+            Action::ShowTab(tab) => self.tab = tab,
+            Action::ToggleMute => self.toggle_mute(),
+            Action::ToggleTimerOnly => self.toggle_layout(PaneLayout::TimerOnly),
+            Action::ToggleTasksOnly => self.toggle_layout(PaneLayout::TasksOnly),
+            Action::CycleSort => self.cycle_sort(),
+            Action::TogglePause => self.toggle_pause(),
+            Action::PageDown => self.page(true),
+            Action::PageUp => self.page(false),
+            Action::FirstTask => self.tasks.select(0, self.clock.now()),
+            Action::LastTask => self
+                .tasks
+                .select(self.tasks.items.len().saturating_sub(1), self.clock.now()),
+        }
+    }
+
+    pub fn handle_command(&mut self, command: control::Command) {
+        match command {
+            control::Command::StartBreak => {
+                if let AppState::Working = self.timer.state {
+                    self.next_period();
+                }
+            }
+            control::Command::AddTime(time) => {
+                self.timer.add_time(time);
+                self.show_toast(format!("Added {}", format::duration(time)));
+            }
+            control::Command::Pause => self.pause(),
+            control::Command::Resume => self.resume(),
+            control::Command::TogglePause => self.toggle_pause(),
+            control::Command::Skip => self.next_period(),
+            control::Command::ToggleTask => self.toggle_current_task(),
+            control::Command::Stop => {
+                if self.timer.state == AppState::Working {
+                    self.interrupt();
+                }
+                self.print_event("stop");
+                self.stopped = true;
+            }
+            control::Command::Log(record) => {
+                let minutes = record.duration().num_minutes();
+                if self.history.insert(record).is_ok() {
+                    self.show_toast(format!("Logged {minutes} min"));
+                }
+            }
+            control::Command::AddTask(task) => self.add_task(&task),
+        }
+    }
+
+    /// Add a task written like in the tasks file, e.g. `Write report ~2`.
+    pub fn add_task(&mut self, text: &str) {
+        let task = Task::parse(text);
+        self.show_toast(format!("Added \"{}\"", task.name));
+        self.tasks.push(task);
+        if self.tasks.state.selected().is_none() {
+            self.tasks.next(self.clock.now());
+        }
+    }
+
+    /// Switch between working and taking a break.
+    pub fn next_period(&mut self) {
+        self.record_period();
+        self.emit(match self.timer.state {
+            AppState::Working => Transition::WorkEnd,
+            AppState::TakingABreak => Transition::BreakEnd,
+        });
+        let long_break = self
+            .timer
+            .next_period(self.clock.monotonic())
+            .contains(&timer::Event::LongBreakDue);
+        self.tasks
+            .set_tracking(self.timer.state == AppState::Working, self.clock.now());
+        match self.timer.state {
+            AppState::TakingABreak => self.break_dismissed = false,
+            AppState::Working => {
+                self.git = git::current();
+                self.next_quote();
+            }
+        }
+        self.period_started_at = self.clock.now();
+
+        self.emit(match self.timer.state {
+            AppState::Working => Transition::WorkStart,
+            AppState::TakingABreak => Transition::BreakStart,
+        });
+        self.update_do_not_disturb();
+        self.alert(long_break);
+        let _ = self.save_session();
+    }
+
+    /// Finish the last period at the end of an `--until` session, and quit.
+    pub fn end_session(&mut self) {
+        self.record_period();
+        if let Some(task) = self.tasks.get_selected_mut() {
+            task.stop_work(self.clock.now());
+        }
+        self.emit(match self.timer.state {
+            AppState::Working => Transition::WorkEnd,
+            AppState::TakingABreak => Transition::BreakEnd,
+        });
+        if !self.muted && !self.is_quiet() {
+            notify::bell();
+        }
+        self.notify("The session is over");
+        self.print_event("session_end");
+        self.stopped = true;
+    }
+
+    /// The pomodoros done since the session started, and the time tracked on each task.
+    pub fn summary(&self) -> String {
+        let (pomodoros, focus) = self
+            .history
+            .pomodoros()
+            .filter(|record| record.start >= self.session_started_at)
+            .fold((0, chrono::Duration::zero()), |(count, time), record| {
+                (count + 1, time + record.duration())
+            });
+        let mut summary = format!(
+            "This session: {pomodoros} pomodoros, {} of focus",
+            format::chrono_duration(focus)
+        );
+        for task in &self.tasks.items {
+            let tracked = task.tracked_duration(self.clock.now());
+            if tracked > chrono::Duration::zero() {
+                let done = if task.is_complete { " (done)" } else { "" };
+                summary += &format!(
+                    "\n  {}{done}: {}",
+                    task.name,
+                    format::chrono_duration(tracked)
+                );
+            }
+        }
+        summary
+    }
+
+    /// Move on to the next quote. Going by the number of pomodoros in the history keeps the
+    /// rotation going across sessions.
+    pub fn next_quote(&mut self) {
+        if !self.quotes.is_empty() {
+            self.quote = Some(self.history.pomodoros().count() % self.quotes.len());
+        }
+    }
+
+    /// Add the period that just ended to the history.
+    pub fn record_period(&mut self) {
+        let record = history::Record {
+            kind: match self.timer.state {
+                AppState::Working => PeriodKind::Work,
+                AppState::TakingABreak => PeriodKind::Break,
+            },
+            start: self.period_started_at,
+            end: self.clock.now(),
+            task: self.get_current_task_name().cloned(),
+            git: match self.timer.state {
+                AppState::Working => self.git.clone(),
+                AppState::TakingABreak => None,
+            },
+        };
+        if let (PeriodKind::Work, Some(checkout), true) =
+            (record.kind, &record.git, self.config.git_commit_template)
+        {
+            let line = format!(
+                "Pomodoro: {} on {}",
+                format::chrono_duration(record.duration()),
+                record.task.as_deref().unwrap_or("no task")
+            );
+            let _ = checkout.append_to_template(&line);
+        }
+        if let (PeriodKind::Work, Some(obsidian)) = (record.kind, self.config.obsidian.clone()) {
+            let record = record.clone();
+            self.background.run(move || {
+                let e = obsidian.log(&record).err()?;
+                Some(format!("Couldn't add to the daily note: {e}"))
+            });
+        }
+        if let (PeriodKind::Work, Some(calendar)) = (record.kind, self.config.calendar.clone()) {
+            let (start, end) = (record.start, record.end);
+            self.background.run(move || {
+                calendar.trim(&calendar::uid(start), start, end);
+                None
+            });
+        }
+        if let (PeriodKind::Work, Some(tracker)) = (record.kind, &self.time_tracker) {
+            tracker.push(time_tracking::Entry::new(
+                record.task.as_deref(),
+                record.start,
+                record.end,
+            ));
+        }
+        if let (PeriodKind::Work, Some(jira)) = (record.kind, &self.config.jira) {
+            if let Some(Remote::Jira(key)) =
+                self.tasks.get_selected().and_then(|t| t.remote.as_ref())
+            {
+                jira.log_work(key, record.start, record.end);
+            }
+        }
+        // Losing a record isn't worth interrupting the session for
+        let _ = self.history.push(record);
+    }
+
+    /// Close the pomodoro being quit in the middle of, keeping what was worked so far.
+    pub fn interrupt(&mut self) {
+        self.interrupted = true;
+        if let Some(task) = self.tasks.get_selected_mut() {
+            task.stop_work(self.clock.now());
+        }
+        let record = history::Record {
+            kind: PeriodKind::Interrupted,
+            start: self.period_started_at,
+            end: self.clock.now(),
+            task: self.get_current_task_name().cloned(),
+            git: self.git.clone(),
+        };
+        // Waits, as pomors is about to quit
+        if let Some(calendar) = &self.config.calendar {
+            calendar.trim(&calendar::uid(record.start), record.start, record.end);
+        }
+        let _ = self.history.push(record);
+    }
+
+    /// Tell the user once when the selected task has taken longer than estimated.
+    pub fn check_estimate(&mut self) {
+        let pomodoro_length = self.config.pomodoro_length;
+        let Some(task) = self.tasks.get_selected_mut() else {
+            return;
+        };
+        let Some(estimate) = task.estimate else {
+            return;
+        };
+        if task.over_estimate || task.is_complete {
+            return;
+        }
+
+        let estimated = pomodoro_length * estimate;
+        if task
+            .tracked_duration(self.clock.now())
+            .to_std()
+            .unwrap_or_default()
+            > estimated
+        {
+            task.over_estimate = true;
+            let message = format!(
+                "\"{}\" is over its estimate of {estimate} pomodoros, re-estimate or split it",
+                task.name
+            );
+            if !self.is_quiet() && !self.muted {
+                notify::bell();
+            }
+            self.notify(&message);
+        }
+    }
+
+    /// Whether we're in the configured quiet hours, where there should only be visual cues.
+    pub fn is_quiet(&self) -> bool {
+        let now = self.clock.local(self.clock.now()).time();
+        self.config
+            .quiet_hours
+            .iter()
+            .any(|quiet_hours| quiet_hours.contains(now))
+    }
+
+    /// Show a desktop notification, with buttons to control the current period.
+    pub fn notify(&self, message: &str) {
+        if self.is_quiet() || !self.config.notifications {
+            return;
+        }
+
+        let add_time = notify::Action {
+            label: "Add 5 min",
+            command: control::Command::AddTime(Duration::from_secs(5 * 60)),
+        };
+        let actions = match self.timer.state {
+            AppState::Working => vec![
+                notify::Action {
+                    label: "Start break now",
+                    command: control::Command::StartBreak,
+                },
+                add_time,
+            ],
+            AppState::TakingABreak => vec![add_time],
+        };
+        notify::desktop_with_actions("pomors", message, actions, self.commands.clone());
+    }
+
+    /// Let the outside world know about a transition.
+    pub fn emit(&self, transition: Transition) {
+        let task = self.get_current_task_name().map(String::as_str);
+        webhook::post(&self.config.webhooks, transition, task);
+        let end = match transition {
+            Transition::WorkStart | Transition::BreakStart => {
+                chrono::Duration::from_std(self.remaining())
+                    .ok()
+                    .map(|remaining| self.clock.now().with_timezone(&Local) + remaining)
+            }
+            Transition::WorkEnd | Transition::BreakEnd => None,
+        };
+        push::send(&self.config.push, transition, task, end);
+        let pomodoros_today = self.history.pomodoros_today(self.clock.as_ref()).count();
+        hooks::run(
+            &self.config.hooks,
+            hooks::Event {
+                transition,
+                task,
+                duration: match transition {
+                    Transition::WorkStart | Transition::BreakStart => self.period_length(),
+                    Transition::WorkEnd | Transition::BreakEnd => self.elapsed(),
+                },
+                pomodoro: match transition {
+                    Transition::WorkStart => pomodoros_today + 1,
+                    _ => pomodoros_today,
+                },
+            },
+        );
+        self.print_event(transition.name());
+    }
+
+    /// Print `event` as a line of JSON with the state of the timer, if asked to with `--events`,
+    /// and send it to the WebSocket clients of the HTTP API.
+    pub fn print_event(&self, event: &str) {
+        let event = serde_json::json!({
+            "event": event,
+            "state": self.timer.state,
+            "task": self.get_current_task_name(),
+            "remaining_secs": self.remaining().as_secs(),
+            "timestamp": self.clock.now(),
+        })
+        .to_string();
+        self.websocket_clients.broadcast(&event);
+        if self.events {
+            println!("{event}");
+        }
+    }
+
+    /// Signal the end of a period, falling back to the terminal bell and a desktop notification
+    /// when there is no audio.
+    pub fn alert(&mut self, long_break: bool) {
+        let message = match self.timer.state {
+            AppState::Working => "Back to work",
+            AppState::TakingABreak if long_break => "Time for a long break",
+            AppState::TakingABreak => "Time for a break",
+        };
+
+        let quiet = self.is_quiet();
+        if self.config.visual_bell != VisualBell::Off || quiet {
+            self.flash_until = Some(self.clock.monotonic() + VISUAL_BELL_LENGTH);
+        }
+        if self.config.visual_bell == VisualBell::Replace || quiet {
+            return;
+        }
+
+        match self.audio.as_mut() {
+            Some(_) | None if self.muted => self.notify(message),
+            Some(audio) => audio.play_alarm(),
+            None => {
+                notify::bell();
+                self.notify(message);
+            }
+        }
+    }
+
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let Some(area) = self.task_list_area.filter(|_| !self.show_help) else {
+            return;
+        };
+        let position = Rect::new(mouse.column, mouse.row, 1, 1);
+        if !area.intersects(position) {
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                // Skip the borders
+                let Some(row) = mouse.row.checked_sub(area.y + 1) else {
+                    return;
+                };
+                if row + 2 >= area.height {
+                    return;
+                }
+                let i = self.tasks.state.offset() + usize::from(row);
+                if i >= self.tasks.items.len() {
+                    return;
+                }
+
+                let now = self.clock.monotonic();
+                let double_click = self.last_click.is_some_and(|(at, clicked)| {
+                    clicked == i && now.duration_since(at) < DOUBLE_CLICK_TIME
+                });
+                self.tasks.select(i, self.clock.now());
+                if double_click {
+                    self.toggle_current_task();
+                    self.last_click = None;
+                } else {
+                    self.last_click = Some((now, i));
+                }
+            }
+            MouseEventKind::ScrollDown => self.tasks.next(self.clock.now()),
+            MouseEventKind::ScrollUp => self.tasks.previous(self.clock.now()),
+            _ => {}
+        }
+    }
+
+    /// Show the task list in the next sort order.
+    pub fn cycle_sort(&mut self) {
+        let sort = self.tasks.sort.next();
+        self.tasks
+            .sort_by(sort, self.config.pomodoro_length, self.clock.now());
+        self.show_toast(format!("Sorted by {}", sort.name()));
+    }
+
+    /// Move the selection a page of the task list down or up.
+    pub fn page(&mut self, down: bool) {
+        let page = self
+            .task_list_area
+            .map_or(1, |area| usize::from(area.height.saturating_sub(2)).max(1));
+        let selected = self.tasks.state.selected().unwrap_or_default();
+        let last = self.tasks.items.len().saturating_sub(1);
+        self.tasks.select(
+            if down {
+                (selected + page).min(last)
+            } else {
+                selected.saturating_sub(page)
+            },
+            self.clock.now(),
+        );
+    }
+
+    /// Switch to `layout`, or back to showing both panes if it's already in use, remembering
+    /// the choice in the config.
+    pub fn toggle_layout(&mut self, layout: PaneLayout) {
+        self.config.layout = if self.config.layout == layout {
+            PaneLayout::Both
+        } else {
+            layout
+        };
+
+        if let Some(config_path) = &self.config_path {
+            // Not worth interrupting the session over
+            let _ = save_config_value(config_path, "layout", &self.config.layout);
+        }
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.show_toast(if self.muted { "Muted" } else { "Unmuted" });
+    }
+
+    /// What the keys do at the moment.
+    pub fn mode(&self) -> keymap::Mode {
+        if self.confirm_quit {
+            keymap::Mode::Confirm
+        } else if self.new_task.is_some() {
+            keymap::Mode::Input
+        } else if self.show_help {
+            keymap::Mode::Help
+        } else if matches!(self.tab, Tab::Timer | Tab::Tasks) {
+            keymap::Mode::Tasks
+        } else {
+            keymap::Mode::View
+        }
+    }
+
+    /// Whether the break is taking over the whole screen.
+    pub fn is_break_takeover(&self) -> bool {
+        self.config.break_takeover
+            && self.timer.state == AppState::TakingABreak
+            && !self.break_dismissed
+    }
+
+    /// `🍅 12:30 – Write report`, for the countdown to show in the terminal or tab title.
+    pub fn terminal_title(&self) -> String {
+        let icon = match self.timer.state {
+            AppState::Working => "🍅",
+            AppState::TakingABreak => "☕",
+        };
+        let remaining = self.remaining().as_secs();
+        let mut title = format!("{icon} {:02}:{:02}", remaining / 60, remaining % 60);
+        if let Some(task) = self.get_current_task_name() {
+            title.push_str(" – ");
+            title.push_str(task);
+        }
+        title
+    }
+
+    /// Briefly show `message` in the corner.
+    pub fn show_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some((message.into(), self.clock.monotonic() + TOAST_LENGTH));
+    }
+
+    /// The toast to show, if it hasn't timed out, and whether it's fading out.
+    pub fn current_toast(&self) -> Option<(&str, bool)> {
+        let (message, until) = self.toast.as_ref()?;
+        let left = until.checked_duration_since(self.clock.monotonic())?;
+        Some((message, left < TOAST_FADE))
+    }
+
+    /// The color of the current period.
+    pub fn state_color(&self) -> Color {
+        match self.timer.state {
+            AppState::Working => self.theme.working,
+            AppState::TakingABreak => self.theme.taking_a_break,
+        }
+    }
+
+    pub fn is_flashing(&self) -> bool {
+        self.flash_until
+            .is_some_and(|flash_until| self.clock.monotonic() < flash_until)
+    }
+
+    /// Keep the do-not-disturb modes, the calendar and the blocked sites in line with the current
+    /// period, if enabled in config.
+    pub fn update_do_not_disturb(&mut self) {
+        let working = self.timer.state == AppState::Working;
+        if let Some(block) = self.config.block.clone() {
+            self.background.run(move || {
+                let blocked = if working {
+                    block.block()
+                } else {
+                    block.unblock()
+                };
+                let e = blocked.err()?;
+                Some(format!("Couldn't change the blocked sites: {e}"))
+            });
+        }
+        let until = self.clock.now().with_timezone(&Local)
+            + chrono::Duration::from_std(self.remaining())
+                .unwrap_or_else(|_| chrono::Duration::zero());
+        if let (AppState::Working, Some(calendar)) =
+            (self.timer.state, self.config.calendar.clone())
+        {
+            let start = self.period_started_at;
+            let end = until.with_timezone(&Utc);
+            self.background.run(move || {
+                calendar.block(&calendar::uid(start), start, end);
+                None
+            });
+        }
+        if let Some(slack) = self.config.slack.clone() {
+            self.background.run(move || {
+                if working {
+                    slack.focus(until);
+                } else {
+                    slack.clear();
+                }
+                None
+            });
+        }
+        if let Some(do_not_disturb) = self.do_not_disturb.clone() {
+            self.background.run(move || {
+                if working {
+                    do_not_disturb.enable();
+                } else {
+                    do_not_disturb.disable();
+                }
+                None
+            });
+        }
+    }
+
+    /// Undo the do-not-disturb modes and the block, clear the status files and announce the end
+    /// of the session on quitting, waiting for Slack so it's not left set.
+    pub fn end_do_not_disturb(&mut self) {
+        // Or the last period's changes could come after these
+        self.background.wait();
+        if let Some(status_file) = &mut self.status_file {
+            let _ = status_file.update(None);
+        }
+        if let Some(block) = &self.config.block {
+            // Whatever's left is lifted on the next start
+            let _ = block.unblock();
+        }
+        if let Some(i3) = &self.config.i3 {
+            i3.end();
+        }
+        if let Some(announce) = &self.config.announce {
+            announce.end();
+        }
+        if let Some(slack) = &self.config.slack {
+            slack.clear();
+        }
+        if let Some(do_not_disturb) = &self.do_not_disturb {
+            do_not_disturb.disable();
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.timer.elapsed(self.clock.monotonic())
+    }
+
+    pub fn pause(&mut self) {
+        if self.timer.pause(self.clock.monotonic()) {
+            self.show_toast("Paused");
+            self.print_event("pause");
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.timer.resume(self.clock.monotonic()) {
+            self.show_toast("Resumed");
+            self.print_event("resume");
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.timer.is_paused() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.timer.remaining(self.clock.monotonic())
+    }
+
+    pub fn toggle_current_task(&mut self) {
+        if let Some(selected_task) = self.tasks.get_selected_mut() {
+            selected_task.is_complete = !selected_task.is_complete;
+            let completed = selected_task.is_complete;
+            let written = match &self.todo_txt {
+                Some(path) => {
+                    let name = &selected_task.name;
+                    todotxt::set_done(path, |text| Task::parse(text).name == *name, completed)
+                }
+                None => Ok(()),
+            };
+            match &selected_task.remote {
+                Some(Remote::Todoist(id)) => {
+                    if let Some(todoist) = &self.config.todoist {
+                        todoist.set_done(id, completed);
+                    }
+                }
+                Some(Remote::GitHub { repo, number }) if completed => {
+                    let tracked =
+                        format::chrono_duration(selected_task.tracked_duration(self.clock.now()));
+                    self.config.github.completed(repo, *number, &tracked);
+                }
+                Some(Remote::GitHub { repo, number }) => self.config.github.reopened(repo, *number),
+                Some(Remote::Jira(_)) | None => {}
+            }
+            self.show_toast(match (written, completed) {
+                (Err(_), _) => "Couldn't update the todo.txt file",
+                (Ok(()), true) => "Task completed",
+                (Ok(()), false) => "Task reopened",
+            });
+            if completed && self.config.auto_advance {
+                self.tasks.select_next_incomplete(self.clock.now());
+            }
+        }
+    }
+
+    /// Add the tasks from Todoist to the list, or the ones from last time if it can't be reached.
+    pub fn add_todoist_tasks(&mut self, cache: &Path) {
+        let Some(todoist) = &self.config.todoist else {
+            return;
+        };
+        let items = match todoist.fetch(cache) {
+            Ok(todoist::Fetched::Todoist(items)) => items,
+            Ok(todoist::Fetched::Cache(items)) => {
+                self.show_toast("Couldn't reach Todoist, these are the tasks from last time");
+                items
+            }
+            Err(_) => {
+                self.show_toast("Couldn't fetch the tasks from Todoist");
+                return;
+            }
+        };
+        for item in items {
+            let mut task = Task::parse(&item.content);
+            task.priority = task.priority.or(item.priority());
+            task.remote = Some(Remote::Todoist(item.id));
+            self.tasks.push(task);
+        }
+    }
+
+    pub fn get_current_task_name(&self) -> Option<&String> {
+        if let Some(selected_task) = self.tasks.get_selected() {
+            Some(&selected_task.name)
+        } else {
+            None
+        }
+    }
+
+    pub fn backspace_task(&mut self) {
+        if let Some(task) = self.tasks.get_selected_mut() {
+            if !task.name.is_empty() {
+                task.name.truncate(task.name.len() - 1)
+            }
+        }
+    }
+}
+
+/// Set up the app, with the tasks from `args` or else those in the tasks file.
+pub fn build_app(args: TimerArgs, mut config: Config, dir: &Path) -> Result<App, Box<dyn Error>> {
+    args.apply(&mut config);
+    // Tasks given for the session take over from the todo.txt file and Todoist
+    let tasks_given = !args.task_list.is_empty()
+        || args.task_file.is_some()
+        || args.github.is_some()
+        || args.jira;
+    let todo_txt = config.todo_txt.clone().filter(|_| !tasks_given);
+    let task_list = if todo_txt.is_some() || args.github.is_some() || args.jira {
+        Vec::new()
+    } else {
+        args.tasks(&dir.join(TASKS_FILE))?
+    };
+
+    let audio = audio::open(
+        config.audio_backend,
+        Path::new(ALARM_SOUND),
+        config.alarm_ramp_up,
+    );
+    let history = History::load(&dir.join("history.json"))?;
+    let mut app = App::new(task_list, config, audio, history, Box::new(SystemClock));
+    app.config_path = Some(pomors_dir().join("config.json"));
+    if let Some(path) = todo_txt {
+        app.todo_txt = Some(path);
+        app.sync_todo_txt();
+    }
+    if let Some(repo) = &args.github {
+        let issues = app
+            .config
+            .github
+            .issues(repo, args.assignee.as_deref())
+            .map_err(|e| format!("couldn't fetch the issues of {repo}: {e}"))?;
+        for issue in issues {
+            let mut task = Task::new(&format!("#{} {}", issue.number, issue.title));
+            task.remote = Some(Remote::GitHub {
+                repo: repo.clone(),
+                number: issue.number,
+            });
+            app.tasks.push(task);
+        }
+    }
+    if args.jira {
+        let jira = app
+            .config
+            .jira
+            .as_ref()
+            .ok_or("`--jira` needs `jira` in the config")?;
+        let issues = jira
+            .issues()
+            .map_err(|e| format!("couldn't fetch the issues from JIRA: {e}"))?;
+        for issue in issues {
+            let mut task = Task::new(&format!("{} {}", issue.key, issue.summary));
+            task.priority = issue.priority;
+            task.remote = Some(Remote::Jira(issue.key));
+            app.tasks.push(task);
+        }
+    }
+    if !tasks_given {
+        app.add_todoist_tasks(&dir.join(TODOIST_CACHE_FILE));
+    }
+    app.presence = app.config.discord.as_ref().map(discord::Presence::start);
+    app.mqtt = app.config.mqtt.as_ref().map(mqtt::Publisher::start);
+    app.do_not_disturb = app.config.do_not_disturb.then(dnd::DoNotDisturb::default);
+    app.i3 = app.config.i3.as_ref().map(i3::Ipc::start);
+    if app.config.status_file {
+        app.status_file = Some(status_file::StatusFile::new(dir));
+    }
+    app.time_tracker = app
+        .config
+        .time_tracking
+        .as_ref()
+        .map(|service| time_tracking::Tracker::start(service, dir.join(TIME_ENTRIES_FILE)));
+    app.listen = args.listen;
+    app.until = args.until.map(next_time_of_day);
+    app.timer.until = app.until.map(|until| {
+        app.clock.monotonic()
+            + (until - app.clock.now().with_timezone(&Local))
+                .to_std()
+                .unwrap_or_default()
+    });
+    let session_path = dir.join(SESSION_FILE);
+    if args.resume {
+        let session =
+            session::Session::load(&session_path)?.ok_or("there's no session to resume")?;
+        app.restore_session(session);
+    }
+    app.session_path = Some(session_path);
+    if args.start_with == Period::Break {
+        app.timer.state = AppState::TakingABreak;
+        app.tasks.set_tracking(false, app.clock.now());
+    }
+    if let Some(quotes_file) = &app.config.quotes_file {
+        app.quotes = fs::read_to_string(quotes_file)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+        app.next_quote();
+    }
+    Ok(app)
+}
+
+/// Start listening for commands, then start the first period. The timer runs for as long as the
+/// returned lock is held.
+pub fn start_session(app: &mut App, dir: &Path) -> Result<lock::Lock, Box<dyn Error>> {
+    let lock = lock::Lock::acquire(dir)?;
+    if let Some(path) = &app.todo_txt {
+        event::watch(path.clone(), app.queue.0.clone());
+    }
+    stop_on_signals(app.commands.clone())?;
+    input::start(&app.config.inputs, app.commands.clone());
+    control::listen(
+        &control::socket_path(dir),
+        app.commands.clone(),
+        app.status.clone(),
+    )?;
+    if let Some(address) = app.listen {
+        let api = http::Api {
+            access: app.config.http.clone(),
+            commands: app.commands.clone(),
+            status: app.status.clone(),
+            tasks: app.tasks_status.clone(),
+            history_path: dir.join("history.json"),
+            clients: app.websocket_clients.clone(),
+        };
+        http::serve(address, api)
+            .map_err(|e| format!("couldn't serve the HTTP API on {address}: {e}"))?;
+    }
+    #[cfg(unix)]
+    if app.config.mpris && mpris::serve(app.commands.clone(), app.status.clone()).is_err() {
+        app.show_toast("Couldn't connect to the session bus for MPRIS");
+    }
+    #[cfg(unix)]
+    if app.config.gnome_pomodoro {
+        if let Err(e) = gnome_pomodoro::serve(app.commands.clone(), app.status.clone()) {
+            app.show_toast(format!("Couldn't offer the gnome-pomodoro interface: {e}"));
+        }
+    }
+
+    // Select the first task, unless a resumed session has one
+    if app.tasks.state.selected().is_none() {
+        app.tasks.next(app.clock.now());
+    }
+    if app.timer.state == AppState::Working {
+        app.git = git::current();
+    }
+    if let Some(announce) = app.config.announce.clone() {
+        let until = app.until;
+        app.background.run(move || {
+            announce.start(until);
+            None
+        });
+    }
+    app.update_do_not_disturb();
+    app.emit(match app.timer.state {
+        AppState::Working => Transition::WorkStart,
+        AppState::TakingABreak => Transition::BreakStart,
+    });
+    Ok(lock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn history_path(test: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pomors-{test}-{}.json", std::process::id()))
+    }
+
+    /// An app working on task `a`.
+    fn app(clock: &ManualClock, history_path: &Path) -> App {
+        let config = Config {
+            pomodoro_length: 25 * MINUTE,
+            break_length: 5 * MINUTE,
+            sound: false,
+            notifications: false,
+            ..Config::default()
+        };
+        let history = History::load(history_path).unwrap();
+        let mut app = App::new(
+            vec!["a".to_string(), "b".to_string()],
+            config,
+            None,
+            history,
+            Box::new(clock.clone()),
+        );
+        app.tasks.next(clock.now());
+        app
+    }
+
+    #[test]
+    fn pomodoro_ends_when_the_clock_says_so() {
+        let clock = ManualClock::new(Utc::now());
+        let history_path = history_path("pomodoro-ends");
+        let mut app = app(&clock, &history_path);
+        clock.advance(24 * MINUTE);
+        app.on_tick();
+        assert_eq!(app.timer.state, AppState::Working);
+        assert_eq!(app.remaining(), MINUTE);
+
+        // It ends on the first tick after its length is up
+        clock.advance(MINUTE);
+        app.on_tick();
+        assert_eq!(app.timer.state, AppState::Working);
+        clock.advance(TICK_RATE);
+        app.on_tick();
+        assert_eq!(app.timer.state, AppState::TakingABreak);
+        let record = app.history.records.last().unwrap();
+        assert_eq!(record.duration().to_std().unwrap(), 25 * MINUTE + TICK_RATE);
+        assert_eq!(record.task.as_deref(), Some("a"));
+        let _ = fs::remove_file(history_path);
+    }
+
+    #[test]
+    fn breathing_keeps_redrawing_during_breaks() {
+        let clock = ManualClock::new(Utc::now());
+        let history_path = history_path("breathing");
+        let mut app = app(&clock, &history_path);
+        clock.advance(MINUTE);
+        app.on_tick();
+        assert!(!app.is_animating());
+
+        clock.advance(24 * MINUTE + TICK_RATE);
+        app.on_tick();
+        assert_eq!(app.timer.state, AppState::TakingABreak);
+        assert!(app.is_breathing());
+        assert_eq!(app.tick_timeout(), TICK_RATE);
+
+        app.pause();
+        assert!(!app.is_breathing());
+        let _ = fs::remove_file(history_path);
+    }
+
+    #[test]
+    fn breaks_are_not_tracked_on_the_task() {
+        let clock = ManualClock::new(Utc::now());
+        let history_path = history_path("breaks-not-tracked");
+        let mut app = app(&clock, &history_path);
+        clock.advance(25 * MINUTE + TICK_RATE);
+        app.on_tick();
+        clock.advance(5 * MINUTE + TICK_RATE);
+        app.on_tick();
+        assert_eq!(app.timer.state, AppState::Working);
+        clock.advance(10 * MINUTE);
+        let task = app.tasks.get_selected().unwrap();
+        assert_eq!(
+            task.tracked_duration(app.clock.now()).to_std().unwrap(),
+            35 * MINUTE + TICK_RATE
+        );
+        let _ = fs::remove_file(history_path);
+    }
+
+    #[test]
+    fn resuming_doesnt_record_the_time_before_quitting_again() {
+        let clock = ManualClock::new(Utc::now());
+        let history_path = history_path("resuming");
+        let session_path = std::env::temp_dir().join(format!(
+            "pomors-resuming-session-{}.json",
+            std::process::id()
+        ));
+        let mut app = app(&clock, &history_path);
+        app.session_path = Some(session_path.clone());
+        clock.advance(10 * MINUTE);
+        app.interrupt();
+        app.close_session();
+
+        clock.advance(60 * MINUTE);
+        let mut app = self::app(&clock, &history_path);
+        let session = session::Session::load(&session_path).unwrap().unwrap();
+        app.restore_session(session);
+        clock.advance(15 * MINUTE + TICK_RATE);
+        app.on_tick();
+        assert_eq!(app.timer.state, AppState::TakingABreak);
+
+        let records = &app.history.records;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].kind, PeriodKind::Interrupted);
+        assert_eq!(records[0].duration(), chrono::Duration::minutes(10));
+        assert_eq!(records[1].kind, PeriodKind::Work);
+        assert_eq!(
+            records[1].duration().to_std().unwrap(),
+            15 * MINUTE + TICK_RATE
+        );
+        let _ = fs::remove_file(history_path);
+        let _ = fs::remove_file(session_path);
+    }
+}
@@ -0,0 +1,146 @@
+//! A TUI for a timer running elsewhere, like `pomors daemon`. It only shows the timer's status
+//! and sends it commands, so it can be closed and opened again without stopping the timer.
+
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use std::{error::Error, path::Path};
+
+use crate::{
+    app::TICK_RATE,
+    bigtext,
+    control::{self, Command, Status},
+    gauge::{GaugeGlyphs, GlyphGauge},
+    theme::Theme,
+    timer::AppState,
+    ui::TerminalGuard,
+};
+
+/// Show the timer listening at `socket` until Esc or q is pressed, or the timer stops.
+pub fn run(socket: &Path, theme: Theme, glyphs: GaugeGlyphs) -> Result<(), Box<dyn Error>> {
+    if control::query_status(socket)?.is_none() {
+        return Err("pomors isn't running".into());
+    }
+
+    let mut guard = TerminalGuard::enter()?;
+    show(&mut guard.terminal, socket, theme, glyphs)
+}
+
+fn show<B: Backend>(
+    terminal: &mut Terminal<B>,
+    socket: &Path,
+    theme: Theme,
+    glyphs: GaugeGlyphs,
+) -> Result<(), Box<dyn Error>> {
+    loop {
+        let Some(status) = control::query_status(socket)? else {
+            return Ok(());
+        };
+        terminal.draw(|f| ui(f, &status, &theme, glyphs))?;
+
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        let command = match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(()),
+            KeyCode::Char(' ') if status.paused => Command::Resume,
+            KeyCode::Char(' ') => Command::Pause,
+            KeyCode::Enter => Command::ToggleTask,
+            KeyCode::Char('n') => Command::Skip,
+            _ => continue,
+        };
+        control::send(socket, &command)?;
+    }
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, status: &Status, theme: &Theme, glyphs: GaugeGlyphs) {
+    let (title, color) = match status.state {
+        AppState::Working => (" Pomodoro ", theme.working),
+        AppState::TakingABreak => (" Break ", theme.taking_a_break),
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    let elapsed = status.length_secs.saturating_sub(status.remaining_secs);
+    let ratio = elapsed as f64 / status.length_secs.max(1) as f64;
+    let gauge = GlyphGauge::new(glyphs)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(Style::default().fg(color)),
+        )
+        .style(Style::default().fg(color))
+        .ratio(ratio)
+        .label(format!("{}%", (ratio * 100.0).round()));
+    f.render_widget(gauge, chunks[0]);
+
+    let clock = format!(
+        "{:02}:{:02}",
+        status.remaining_secs / 60,
+        status.remaining_secs % 60
+    );
+    let mut lines = Vec::new();
+    match bigtext::render(&clock) {
+        Some(big_clock)
+            if usize::from(chunks[1].width) >= bigtext::width(&clock)
+                && usize::from(chunks[1].height) > bigtext::HEIGHT + 1 =>
+        {
+            lines.extend(
+                big_clock
+                    .into_iter()
+                    .map(|line| Line::from(Span::styled(line, Style::default().fg(color)))),
+            );
+        }
+        _ => lines.push(Line::from(Span::styled(clock, Style::default().fg(color)))),
+    }
+    if status.paused {
+        lines.push(Line::from(Span::styled(
+            "⏸ paused",
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+    }
+    if let Some(task) = &status.task {
+        lines.push(Line::from(format!("▶ {task}")));
+    }
+    f.render_widget(
+        Paragraph::new(lines).alignment(Alignment::Center),
+        chunks[1],
+    );
+
+    let key = Style::default()
+        .fg(theme.accent)
+        .add_modifier(Modifier::BOLD);
+    let hint = Style::default().fg(theme.muted);
+    let hints = [
+        ("Space", if status.paused { "resume" } else { "pause" }),
+        ("Enter", "done"),
+        ("n", "skip"),
+        ("Esc", "detach"),
+    ];
+    let spans: Vec<Span> = hints
+        .into_iter()
+        .flat_map(|(name, action)| {
+            [
+                Span::styled(name, key),
+                Span::styled(format!(" {action}  "), hint),
+            ]
+        })
+        .collect();
+    f.render_widget(Paragraph::new(Line::from(spans)), chunks[2]);
+}
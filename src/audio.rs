@@ -0,0 +1,110 @@
+//! Playing the alarm, behind a trait so the audio library can be swapped out.
+
+use serde::{Deserialize, Serialize};
+use std::{path::Path, time::Duration};
+
+/// Something that can play the alarm sound.
+pub trait AudioSink {
+    /// Start playing the alarm, without waiting for it to finish.
+    fn play_alarm(&mut self);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioBackend {
+    RustyAudio,
+    /// Needs the `rodio` feature
+    Rodio,
+    /// Rings the terminal bell and sends a desktop notification instead
+    None,
+}
+
+/// Set up the audio once, returning `None` when it can't be used or is turned off. The alarm fades in over
+/// `ramp_up`, on backends with volume control.
+pub fn open(
+    backend: AudioBackend,
+    alarm_sound: &Path,
+    ramp_up: Duration,
+) -> Option<Box<dyn AudioSink>> {
+    if !alarm_sound.is_file() {
+        return None;
+    }
+
+    match backend {
+        AudioBackend::RustyAudio => {
+            RustyAudioSink::open(alarm_sound).map(|sink| Box::new(sink) as Box<dyn AudioSink>)
+        }
+        #[cfg(feature = "rodio")]
+        AudioBackend::Rodio => rodio_sink::RodioSink::open(alarm_sound, ramp_up)
+            .map(|sink| Box::new(sink) as Box<dyn AudioSink>),
+        #[cfg(not(feature = "rodio"))]
+        AudioBackend::Rodio => {
+            let _ = ramp_up;
+            None
+        }
+        AudioBackend::None => None,
+    }
+}
+
+const ALARM: &str = "alarm";
+
+/// Has no volume control, so always plays the alarm at full volume.
+pub struct RustyAudioSink(rusty_audio::Audio);
+
+impl RustyAudioSink {
+    fn open(alarm_sound: &Path) -> Option<Self> {
+        let mut audio = rusty_audio::Audio::new();
+        if audio.disabled() {
+            return None;
+        }
+        // Panics on a missing file, which `open` checks for
+        audio.add(ALARM, alarm_sound);
+        Some(Self(audio))
+    }
+}
+
+impl AudioSink for RustyAudioSink {
+    fn play_alarm(&mut self) {
+        // Execution continues while playback occurs in another thread.
+        self.0.play(ALARM)
+    }
+}
+
+#[cfg(feature = "rodio")]
+mod rodio_sink {
+    use rodio::{source::Buffered, Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+    use std::{fs, io::Cursor, path::Path, time::Duration};
+
+    use super::AudioSink;
+
+    pub struct RodioSink {
+        // Playback stops when the stream is dropped
+        _stream: OutputStream,
+        handle: OutputStreamHandle,
+        alarm: Buffered<Decoder<Cursor<Vec<u8>>>>,
+        ramp_up: Duration,
+    }
+
+    impl RodioSink {
+        pub fn open(alarm_sound: &Path, ramp_up: Duration) -> Option<Self> {
+            let (stream, handle) = OutputStream::try_default().ok()?;
+            let bytes = fs::read(alarm_sound).ok()?;
+            let alarm = Decoder::new(Cursor::new(bytes)).ok()?.buffered();
+            Some(Self {
+                _stream: stream,
+                handle,
+                alarm,
+                ramp_up,
+            })
+        }
+    }
+
+    impl AudioSink for RodioSink {
+        fn play_alarm(&mut self) {
+            if let Ok(sink) = Sink::try_new(&self.handle) {
+                sink.append(self.alarm.clone().fade_in(self.ramp_up));
+                sink.detach();
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Slow work, like running commands and calling services, done in order on a thread of its own
+//! so it never holds up the timer or the UI. What a job has to tell the user comes back on the
+//! event channel.
+
+use std::{
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use crate::event::Event;
+
+/// A job returns a message to show, if it has one.
+type Job = Box<dyn FnOnce() -> Option<String> + Send>;
+
+pub struct Worker {
+    jobs: Sender<Job>,
+}
+
+impl Worker {
+    pub fn start(events: Sender<Event>) -> Worker {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        thread::spawn(move || {
+            for job in queue {
+                if let Some(message) = job() {
+                    let _ = events.send(Event::Message(message));
+                }
+            }
+        });
+        Worker { jobs }
+    }
+
+    /// Run `job` after those already queued.
+    pub fn run(&self, job: impl FnOnce() -> Option<String> + Send + 'static) {
+        let _ = self.jobs.send(Box::new(job));
+    }
+
+    /// Wait for the queued jobs to finish, so nothing's left half done on quitting.
+    pub fn wait(&self) {
+        let (done, finished) = mpsc::channel();
+        self.run(move || {
+            let _ = done.send(());
+            None
+        });
+        let _ = finished.recv();
+    }
+}
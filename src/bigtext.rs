@@ -0,0 +1,45 @@
+//! Big block digits, for a timer that can be read from across the room.
+
+pub const HEIGHT: usize = 5;
+
+const fn glyph(c: char) -> Option<[&'static str; HEIGHT]> {
+    Some(match c {
+        '0' => ["███", "█ █", "█ █", "█ █", "███"],
+        '1' => [" █ ", "██ ", " █ ", " █ ", "███"],
+        '2' => ["███", "  █", "███", "█  ", "███"],
+        '3' => ["███", "  █", " ██", "  █", "███"],
+        '4' => ["█ █", "█ █", "███", "  █", "  █"],
+        '5' => ["███", "█  ", "███", "  █", "███"],
+        '6' => ["███", "█  ", "███", "█ █", "███"],
+        '7' => ["███", "  █", "  █", "  █", "  █"],
+        '8' => ["███", "█ █", "███", "█ █", "███"],
+        '9' => ["███", "█ █", "███", "  █", "███"],
+        ':' => [" ", "█", " ", "█", " "],
+        ' ' => [" ", " ", " ", " ", " "],
+        _ => return None,
+    })
+}
+
+/// Render `text` as `HEIGHT` lines of block characters, each one doubled horizontally so the
+/// digits aren't too narrow. `None` if there's a character without a glyph.
+pub fn render(text: &str) -> Option<[String; HEIGHT]> {
+    let mut lines: [String; HEIGHT] = Default::default();
+    for (i, c) in text.chars().enumerate() {
+        let glyph = glyph(c)?;
+        for (line, row) in lines.iter_mut().zip(glyph) {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            for block in row.chars() {
+                line.push(block);
+                line.push(block);
+            }
+        }
+    }
+    Some(lines)
+}
+
+/// How many columns `render` needs for `text`.
+pub fn width(text: &str) -> usize {
+    render(text).map_or(0, |lines| lines[0].chars().count())
+}
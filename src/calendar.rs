@@ -0,0 +1,97 @@
+//! Blocking out each pomodoro as a busy event on a CalDAV calendar, so colleagues see it.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Calendar {
+    /// The calendar collection, e.g. `https://nextcloud.example.com/remote.php/dav/calendars/me/personal/`
+    /// or `https://apidata.googleusercontent.com/caldav/v2/me@gmail.com/events/`
+    pub url: String,
+    /// Without it the password is sent as a bearer token, as Google takes an OAuth access token
+    #[serde(default)]
+    pub username: Option<String>,
+    pub password: String,
+    /// What the events are called
+    #[serde(default = "default_summary")]
+    pub summary: String,
+}
+
+fn default_summary() -> String {
+    "Focus".to_string()
+}
+
+/// Pomodoros cut shorter than this are taken off the calendar instead of trimmed.
+const SHORTEST_EVENT_SECS: i64 = 60;
+
+impl Calendar {
+    fn request(&self, method: &str, uid: &str) -> ureq::Request {
+        let authorization = match &self.username {
+            Some(username) => format!(
+                "Basic {}",
+                STANDARD.encode(format!("{username}:{}", self.password))
+            ),
+            None => format!("Bearer {}", self.password),
+        };
+        let url = format!("{}/{uid}.ics", self.url.trim_end_matches('/'));
+        ureq::request(method, &url)
+            .set("Authorization", &authorization)
+            .timeout(TIMEOUT)
+    }
+
+    /// Put the event `uid` from `start` to `end` on the calendar, or move its end if it's there.
+    /// Failures are ignored, the pomodoro just doesn't show.
+    pub fn block(&self, uid: &str, start: DateTime<Utc>, end: DateTime<Utc>) {
+        let event = format!(
+            "BEGIN:VCALENDAR\r\n\
+             VERSION:2.0\r\n\
+             PRODID:-//pomors//EN\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:{uid}\r\n\
+             DTSTAMP:{}\r\n\
+             DTSTART:{}\r\n\
+             DTEND:{}\r\n\
+             SUMMARY:{}\r\n\
+             TRANSP:OPAQUE\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+            ical_time(Utc::now()),
+            ical_time(start),
+            ical_time(end),
+            escape(&self.summary)
+        );
+        let _ = self
+            .request("PUT", uid)
+            .set("Content-Type", "text/calendar; charset=utf-8")
+            .send_string(&event);
+    }
+
+    /// Cut the event `uid` short at `end`, taking it off if barely anything was worked.
+    pub fn trim(&self, uid: &str, start: DateTime<Utc>, end: DateTime<Utc>) {
+        if (end - start).num_seconds() < SHORTEST_EVENT_SECS {
+            let _ = self.request("DELETE", uid).call();
+        } else {
+            self.block(uid, start, end);
+        }
+    }
+}
+
+/// The event for the pomodoro started at `start`.
+pub fn uid(start: DateTime<Utc>) -> String {
+    format!("pomors-{}", start.timestamp_millis())
+}
+
+fn ical_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', r"\\")
+        .replace(';', r"\;")
+        .replace(',', r"\,")
+        .replace('\n', r"\n")
+}
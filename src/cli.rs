@@ -0,0 +1,683 @@
+//! Command line arguments, and the subcommands that don't need the TUI.
+
+use crate::{
+    clock::SystemClock,
+    config::Config,
+    control::Status,
+    format,
+    history::{History, PeriodKind, Record},
+    theme::ThemeName,
+    timer::AppState,
+};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Print JSON from stats, status, export and config, for scripts
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Run or control a separate timer with its own history, tasks and session
+    #[arg(long, global = true, value_parser = parse_instance)]
+    pub instance: Option<String>,
+
+    /// Without a subcommand, the timer is started with these
+    #[command(flatten)]
+    pub start: StartArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run the timer, which is also what happens without a subcommand
+    Start(StartArgs),
+    /// Run the timer in the background without the TUI, controlled through its socket
+    Daemon(DaemonArgs),
+    /// Run a single timer, with its progress on one line instead of the TUI
+    Once {
+        /// How long, e.g. 25m, 1h30m or 90s
+        #[arg(value_parser = parse_length)]
+        duration: Duration,
+        /// What the time is spent on
+        task: Option<String>,
+        #[command(flatten)]
+        alerts: AlertArgs,
+    },
+    /// Print what the running instance is doing, for status bars
+    Status {
+        #[arg(long, value_enum, default_value_t = StatusFormat::Plain)]
+        format: StatusFormat,
+    },
+    /// Print a short segment for the tmux status line, same as `status --format tmux`
+    Tmux,
+    /// Show the running timer in the TUI, without stopping it when the TUI is closed
+    Attach,
+    /// Pause the running timer
+    Pause,
+    /// Resume the running timer
+    Resume,
+    /// End the running timer's current period straight away
+    Skip,
+    /// Quit the running timer
+    Stop,
+    /// Mark the running timer's selected task as done or not done
+    ToggleTask,
+    /// Print the work and break periods a session would have, with their times
+    Plan(PlanArgs),
+    /// Print the shell completion script for `shell`
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Show the number of pomodoros and focus time, today and in total
+    Stats,
+    /// Show where the config file is and the config in use
+    Config,
+    /// Print the history as CSV, JSON or org-mode clock entries
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+    },
+    /// Record work done away from the timer in the history
+    Log {
+        /// What the time was spent on
+        task: String,
+        /// How long, e.g. 45m or 1h30m
+        #[arg(value_parser = format::parse_duration)]
+        duration: Duration,
+        /// When the work started, e.g. "2024-05-02 14:00" or 14:00 for today. Defaults to
+        /// `duration` ago
+        #[arg(long, value_parser = format::parse_date_time)]
+        at: Option<NaiveDateTime>,
+    },
+    /// Add tasks to the task list used when none are given with --task-list
+    Add {
+        /// Tasks to add, e.g. "Write report ~3"
+        #[arg(required = true)]
+        tasks: Vec<String>,
+    },
+}
+
+/// Options for running the timer, with or without the TUI.
+#[derive(Args, Debug, Default)]
+pub struct TimerArgs {
+    /// List of tasks, or - to read them from stdin, one per line
+    #[clap(short, long, value_parser, num_args = 1.., value_delimiter = ',')]
+    pub task_list: Vec<String>,
+
+    /// File to read the tasks from, one per line
+    #[arg(long, conflicts_with = "task_list")]
+    pub task_file: Option<PathBuf>,
+
+    /// todo.txt file to take the tasks from and keep in sync, overrides the config
+    #[arg(long, conflicts_with_all = ["task_list", "task_file"])]
+    pub todo_txt: Option<PathBuf>,
+
+    /// GitHub repository to take the open issues from as tasks
+    #[arg(long, value_name = "OWNER/REPO", value_parser = parse_repo,
+          conflicts_with_all = ["task_list", "task_file", "todo_txt"])]
+    pub github: Option<String>,
+
+    /// Only take the GitHub issues assigned to this user, or to `me`
+    #[arg(long, requires = "github")]
+    pub assignee: Option<String>,
+
+    /// Take the JIRA issues found by the `jira` config as tasks, and log pomodoros on them
+    #[arg(long, conflicts_with_all = ["task_list", "task_file", "todo_txt", "github"])]
+    pub jira: bool,
+
+    #[command(flatten)]
+    pub lengths: LengthArgs,
+
+    /// Continue the last session, if it was quit in the middle of a pomodoro or didn't get to
+    /// exit, with its tasks and time left
+    #[arg(long, conflicts_with_all = ["task_list", "task_file", "todo_txt", "github", "jira", "start_with"])]
+    pub resume: bool,
+
+    /// Whether the first period is a pomodoro or a break
+    #[arg(long, value_enum, default_value_t = Period::Work)]
+    pub start_with: Period,
+
+    /// Time of day to end the session at, e.g. 17:30, shortening or stretching the last period
+    #[arg(long, value_parser = format::parse_time_of_day)]
+    pub until: Option<NaiveTime>,
+
+    #[command(flatten)]
+    pub alerts: AlertArgs,
+
+    /// Address to serve the HTTP API on, e.g. 127.0.0.1:8080
+    #[arg(long, value_name = "ADDRESS")]
+    pub listen: Option<SocketAddr>,
+}
+
+impl TimerArgs {
+    /// Override `config` with the options that were given.
+    pub fn apply(&self, config: &mut Config) {
+        self.lengths.apply(config);
+        self.alerts.apply(config);
+        if let Some(todo_txt) = &self.todo_txt {
+            config.todo_txt = Some(todo_txt.clone());
+        }
+    }
+
+    /// Whether the tasks were given on the command line, or where to take them from, which a
+    /// resumed session's tasks would replace.
+    pub fn has_task_source(&self) -> bool {
+        !self.task_list.is_empty()
+            || self.task_file.is_some()
+            || self.todo_txt.is_some()
+            || self.github.is_some()
+            || self.jira
+    }
+
+    /// The tasks given on the command line, from stdin or from `--task-file`, falling back on the
+    /// task list file at `tasks_path`.
+    pub fn tasks(&self, tasks_path: &Path) -> io::Result<Vec<String>> {
+        if let Some(task_file) = &self.task_file {
+            return Ok(task_lines(&fs::read_to_string(task_file)?));
+        }
+        match self.task_list.as_slice() {
+            [] => read_tasks(tasks_path),
+            [stdin] if stdin == "-" => Ok(task_lines(&io::read_to_string(io::stdin())?)),
+            task_list => Ok(task_list.to_vec()),
+        }
+    }
+}
+
+fn parse_repo(repo: &str) -> Result<String, String> {
+    match repo.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+            Ok(repo.to_string())
+        }
+        _ => Err("expected owner/repo".to_string()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Period {
+    #[default]
+    Work,
+    Break,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct DaemonArgs {
+    #[command(flatten)]
+    pub timer: TimerArgs,
+
+    /// Print a line of JSON to stdout for every transition, pause, resume and the last minute
+    /// of each period
+    #[arg(long)]
+    pub events: bool,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct PlanArgs {
+    #[command(flatten)]
+    pub lengths: LengthArgs,
+
+    /// Number of cycles to plan, one unless there's --until
+    #[arg(long)]
+    pub cycles: Option<u32>,
+
+    /// Time of day to end the session at, e.g. 17:30
+    #[arg(long, value_parser = format::parse_time_of_day)]
+    pub until: Option<NaiveTime>,
+}
+
+/// The lengths of periods, overriding the config.
+#[derive(Args, Debug, Default)]
+pub struct LengthArgs {
+    /// Length of one pomodoro, e.g. 25m, 1h30m or 90s, in minutes without a unit
+    #[arg(short, long, value_parser = parse_length)]
+    pub length: Option<Duration>,
+
+    /// Length of the short breaks
+    #[arg(long = "break", value_parser = parse_length)]
+    pub break_length: Option<Duration>,
+
+    /// Length of the long break at the end of each cycle
+    #[arg(long, value_parser = parse_length)]
+    pub long_break: Option<Duration>,
+
+    /// Number of pomodoros in a cycle, before the long break
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub cycle: Option<u32>,
+}
+
+impl LengthArgs {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(length) = self.length {
+            config.pomodoro_length = length;
+        }
+        if let Some(break_length) = self.break_length {
+            config.break_length = break_length;
+        }
+        if let Some(long_break) = self.long_break {
+            config.long_break_length = long_break;
+        }
+        if let Some(cycle) = self.cycle {
+            config.pomodoros_per_cycle = cycle;
+        }
+    }
+}
+
+/// An instance name, which names its directory.
+fn parse_instance(name: &str) -> Result<String, String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(name.to_string())
+    } else {
+        Err("use only letters, digits, - and _".to_string())
+    }
+}
+
+/// A duration for a period, which can't be zero.
+fn parse_length(text: &str) -> Result<Duration, String> {
+    let length = format::parse_duration(text)?;
+    if length.is_zero() {
+        return Err("a period can't be zero long".to_string());
+    }
+    Ok(length)
+}
+
+/// Options to keep a session quiet, e.g. in meetings or while recording.
+#[derive(Args, Debug, Default)]
+pub struct AlertArgs {
+    /// Don't play any sounds, overrides the config
+    #[arg(long)]
+    pub no_sound: bool,
+
+    /// Don't show desktop notifications, overrides the config
+    #[arg(long)]
+    pub no_notify: bool,
+}
+
+impl AlertArgs {
+    pub fn apply(&self, config: &mut Config) {
+        if self.no_sound {
+            config.sound = false;
+        }
+        if self.no_notify {
+            config.notifications = false;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatusFormat {
+    /// `🍅 12:30 Write report`
+    Plain,
+    /// JSON for a Waybar custom module
+    Waybar,
+    /// Text with Polybar color tags
+    Polybar,
+    /// A short segment with tmux color tags, without the task
+    Tmux,
+    /// The status as is, `null` when nothing is running
+    Json,
+    /// `W 12:30`, `B 04:10` or `P 12:30` when paused, for shell prompts
+    Prompt,
+    /// A JSON block for i3bar, e.g. through i3blocks
+    I3bar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// `kind,start,end,task,repo,branch` lines
+    Csv,
+    /// The records as they're stored
+    Json,
+    /// A heading per task with the work periods as `CLOCK:` entries in its logbook
+    Org,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct StartArgs {
+    #[command(flatten)]
+    pub timer: TimerArgs,
+
+    /// Color theme, overrides the config
+    #[arg(long, value_enum)]
+    pub theme: Option<ThemeName>,
+
+    /// Use the terminal's own colors, like setting NO_COLOR
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Show a single line instead of the full UI, as is done on tiny terminals
+    #[arg(long)]
+    pub compact: bool,
+}
+
+pub fn completions(shell: Shell) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+}
+
+/// Print `status` in `format`.
+pub fn status(status: Option<Status>, format: StatusFormat) -> serde_json::Result<()> {
+    println!("{}", format_status(status.as_ref(), format)?);
+    Ok(())
+}
+
+/// `status` in `format`. Nothing running is an empty line, or an idle module for Waybar.
+pub fn format_status(status: Option<&Status>, format: StatusFormat) -> serde_json::Result<String> {
+    let Some(status) = status else {
+        return Ok(match format {
+            StatusFormat::Waybar => serde_json::json!({ "text": "", "class": "idle" }).to_string(),
+            StatusFormat::Json => "null".to_string(),
+            StatusFormat::I3bar => serde_json::json!({ "full_text": "" }).to_string(),
+            StatusFormat::Plain
+            | StatusFormat::Polybar
+            | StatusFormat::Tmux
+            | StatusFormat::Prompt => String::new(),
+        });
+    };
+
+    let (mut icon, class, color, tmux_color) = match status.state {
+        AppState::Working => ("🍅", "working", "#e06c75", "red"),
+        AppState::TakingABreak => ("☕", "break", "#98c379", "green"),
+    };
+    if status.paused {
+        icon = "⏸";
+    }
+    let remaining = status.remaining_secs;
+    let clock = format!("{:02}:{:02}", remaining / 60, remaining % 60);
+    let text = format!("{icon} {clock}");
+    let task = status.task.clone().unwrap_or_default();
+
+    Ok(match format {
+        StatusFormat::Plain => format!("{text} {task}").trim_end().to_string(),
+        StatusFormat::Polybar => format!("%{{F{color}}}{text}%{{F-}} {task}")
+            .trim_end()
+            .to_string(),
+        StatusFormat::Tmux => format!("#[fg={tmux_color}]{text}#[default]"),
+        StatusFormat::Json => serde_json::to_string(status)?,
+        StatusFormat::Prompt => {
+            let letter = match status.state {
+                _ if status.paused => 'P',
+                AppState::Working => 'W',
+                AppState::TakingABreak => 'B',
+            };
+            format!("{letter} {clock}")
+        }
+        StatusFormat::I3bar => {
+            let block = serde_json::json!({
+                "full_text": format!("{text} {task}").trim_end(),
+                "short_text": text,
+                "color": color,
+            });
+            serde_json::to_string(&block)?
+        }
+        StatusFormat::Waybar => {
+            let elapsed = status.length_secs.saturating_sub(remaining);
+            let module = serde_json::json!({
+                "text": text,
+                "tooltip": task,
+                "class": class,
+                "percentage": elapsed * 100 / status.length_secs.max(1),
+            });
+            serde_json::to_string(&module)?
+        }
+    })
+}
+
+/// Print the periods of a session starting at `start`, the way the timer would run them. It ends
+/// at `until` or after `cycles` cycles, whichever comes first, or after one cycle without either.
+pub fn plan(
+    config: &Config,
+    start: DateTime<Local>,
+    until: Option<DateTime<Local>>,
+    cycles: Option<u32>,
+) {
+    let cycles = cycles.or(if until.is_some() { None } else { Some(1) });
+    // Periods are fitted in before `until` like the timer does
+    let fit = |length: Duration, time: DateTime<Local>, next_break: Option<Duration>| match until {
+        Some(until) => {
+            let time_left = (until - time).to_std().unwrap_or_default();
+            crate::timer::fit_period(length, time_left, next_break)
+        }
+        None => length,
+    };
+
+    let mut time = start;
+    let mut pomodoros = 0;
+    let mut pomodoros_in_cycle = 0;
+    let mut cycles_done = 0;
+    let mut focus = Duration::ZERO;
+    while cycles != Some(cycles_done) {
+        let is_long_break = pomodoros_in_cycle + 1 >= config.pomodoros_per_cycle;
+        let break_length = if is_long_break {
+            config.long_break_length
+        } else {
+            config.break_length
+        };
+
+        let length = fit(config.pomodoro_length, time, Some(break_length));
+        if length.is_zero() {
+            break;
+        }
+        pomodoros += 1;
+        pomodoros_in_cycle += 1;
+        focus += length;
+        time = print_period(time, length, &format!("pomodoro {pomodoros}"));
+
+        let length = fit(break_length, time, None);
+        if length.is_zero() {
+            break;
+        }
+        if is_long_break {
+            pomodoros_in_cycle = 0;
+            cycles_done += 1;
+            time = print_period(time, length, "long break");
+        } else {
+            time = print_period(time, length, "break");
+        }
+    }
+    println!(
+        "Ends at {}, {pomodoros} pomodoros, {} of focus",
+        time.format("%H:%M"),
+        format::duration(focus)
+    );
+}
+
+/// Print a line for a period starting at `start`, returning when it ends.
+fn print_period(start: DateTime<Local>, length: Duration, name: &str) -> DateTime<Local> {
+    let end =
+        start + chrono::Duration::from_std(length).unwrap_or_else(|_| chrono::Duration::zero());
+    println!("{}–{}  {name}", start.format("%H:%M"), end.format("%H:%M"));
+    end
+}
+
+pub fn stats(history: &History, json: bool) -> serde_json::Result<()> {
+    let total = |records: &mut dyn Iterator<Item = &Record>| {
+        records.fold((0, chrono::Duration::zero()), |(count, time), record| {
+            (count + 1, time + record.duration())
+        })
+    };
+    let today = total(&mut history.pomodoros_today(&SystemClock));
+    let all_time = total(&mut history.pomodoros());
+
+    if json {
+        let period = |(count, time): (u32, chrono::Duration)| serde_json::json!({ "pomodoros": count, "focus_secs": time.num_seconds() });
+        let stats = serde_json::json!({ "today": period(today), "all_time": period(all_time) });
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        let text = |(count, time): (u32, chrono::Duration)| {
+            format!("{count} pomodoros, {} focus", format::chrono_duration(time))
+        };
+        println!("Today: {}", text(today));
+        println!("All time: {}", text(all_time));
+    }
+    Ok(())
+}
+
+pub fn config(config_path: &Path, config: &Config, json: bool) -> serde_json::Result<()> {
+    if json {
+        let config = serde_json::json!({ "path": config_path, "config": config });
+        println!("{}", serde_json::to_string_pretty(&config)?);
+    } else {
+        println!("{}", config_path.display());
+        println!("{}", serde_json::to_string_pretty(config)?);
+    }
+    Ok(())
+}
+
+/// Print the history in `format`.
+pub fn export(history: &History, format: ExportFormat) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    match format {
+        ExportFormat::Csv => export_csv(history, &mut stdout),
+        ExportFormat::Json => Ok(writeln!(
+            stdout,
+            "{}",
+            serde_json::to_string_pretty(&history.records)?
+        )?),
+        ExportFormat::Org => export_org(history, &mut stdout),
+    }
+}
+
+fn export_csv(history: &History, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "kind,start,end,task,repo,branch")?;
+    for record in &history.records {
+        let (repo, branch) = match &record.git {
+            Some(checkout) => (checkout.repo.to_string_lossy(), checkout.branch.as_str()),
+            None => (Default::default(), ""),
+        };
+        writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            serde_json::to_value(record.kind)?
+                .as_str()
+                .unwrap_or_default(),
+            record.start.to_rfc3339(),
+            record.end.to_rfc3339(),
+            csv_field(record.task.as_deref().unwrap_or_default()),
+            csv_field(&repo),
+            csv_field(branch)
+        )?;
+    }
+    Ok(())
+}
+
+/// The work periods as org-mode clock entries, under a heading per task in the order the tasks
+/// were first worked on.
+fn export_org(history: &History, out: &mut impl Write) -> io::Result<()> {
+    let mut tasks: Vec<(&str, Vec<&Record>)> = Vec::new();
+    let work = history
+        .records
+        .iter()
+        .filter(|record| record.kind != PeriodKind::Break);
+    for record in work {
+        let task = record.task.as_deref().unwrap_or("No task");
+        match tasks.iter_mut().find(|(name, _)| *name == task) {
+            Some((_, records)) => records.push(record),
+            None => tasks.push((task, vec![record])),
+        }
+    }
+
+    let timestamp = |time: DateTime<Utc>| {
+        time.with_timezone(&Local)
+            .format("[%Y-%m-%d %a %H:%M]")
+            .to_string()
+    };
+    for (task, records) in tasks {
+        writeln!(out, "* {task}")?;
+        writeln!(out, "  :LOGBOOK:")?;
+        // Newest first, like org-mode adds them
+        for record in records.iter().rev() {
+            let minutes = record.duration().num_minutes();
+            writeln!(
+                out,
+                "  CLOCK: {}--{} => {:2}:{:02}",
+                timestamp(record.start),
+                timestamp(record.end),
+                minutes / 60,
+                minutes % 60
+            )?;
+        }
+        writeln!(out, "  :END:")?;
+    }
+    Ok(())
+}
+
+/// Quote `field` if it has anything CSV would misread.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A pomodoro of `duration` on `task`, started `at` or ending now.
+pub fn log(
+    task: String,
+    duration: Duration,
+    at: Option<NaiveDateTime>,
+) -> Result<Record, Box<dyn Error>> {
+    let duration = chrono::Duration::from_std(duration)?;
+    let start = match at {
+        Some(at) => Local
+            .from_local_datetime(&at)
+            .earliest()
+            .ok_or_else(|| format!("{at} doesn't exist in the local time zone"))?
+            .with_timezone(&Utc),
+        None => Utc::now() - duration,
+    };
+    Ok(Record {
+        kind: PeriodKind::Work,
+        start,
+        end: start + duration,
+        task: Some(task),
+        git: None,
+    })
+}
+
+/// Append `tasks` to the task list file, one per line.
+pub fn add(tasks_path: &Path, tasks: &[String]) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(tasks_path)?;
+    for task in tasks {
+        writeln!(file, "{}", task.trim())?;
+    }
+    Ok(())
+}
+
+/// The tasks in the task list file, which is empty if there isn't one.
+pub fn read_tasks(tasks_path: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(tasks_path) {
+        Ok(tasks) => Ok(task_lines(&tasks)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// One task per non-empty line.
+fn task_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -0,0 +1,77 @@
+//! Where the time comes from, so tests and previews can move it on themselves instead of waiting
+//! for it to pass.
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+pub trait Clock {
+    /// The time of day, for records and what's shown
+    fn now(&self) -> DateTime<Utc>;
+    /// For measuring time, which unlike `now` never jumps
+    fn monotonic(&self) -> Instant;
+    /// `time` in the time zone the clock is in, for the time of day and the date
+    fn local(&self, time: DateTime<Utc>) -> DateTime<FixedOffset>;
+}
+
+/// The clocks of the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn monotonic(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn local(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        time.with_timezone(&Local).fixed_offset()
+    }
+}
+
+/// A clock that only moves when it's told to, in UTC. Clones share the same time.
+#[derive(Clone)]
+pub struct ManualClock {
+    start: DateTime<Utc>,
+    start_instant: Instant,
+    passed: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    /// A clock stopped at `start`.
+    pub fn new(start: DateTime<Utc>) -> ManualClock {
+        ManualClock {
+            start,
+            start_instant: Instant::now(),
+            passed: Arc::default(),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        if let Ok(mut passed) = self.passed.lock() {
+            *passed += by;
+        }
+    }
+
+    fn passed(&self) -> Duration {
+        self.passed.lock().map_or(Duration::ZERO, |passed| *passed)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.start + chrono::Duration::from_std(self.passed()).unwrap_or_default()
+    }
+
+    fn monotonic(&self) -> Instant {
+        self.start_instant + self.passed()
+    }
+
+    fn local(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        time.fixed_offset()
+    }
+}
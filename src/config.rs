@@ -0,0 +1,241 @@
+//! The settings in `config.json`.
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    announce, audio::AudioBackend, calendar, discord, distractions, gauge::GaugeGlyphs, github,
+    hooks, http, i3, input, jira, mqtt, obsidian, push, slack, theme::ThemeName, time_tracking,
+    todoist, webhook,
+};
+
+pub const ALARM_SOUND: &str = "creepy-church-bell-33827.mp3";
+
+/// Flashing the screen on transitions, for when the alarm can't be heard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisualBell {
+    Off,
+    /// Flash as well as play the alarm
+    Augment,
+    /// Flash instead of playing the alarm
+    Replace,
+}
+
+/// A time window, e.g. 21:00 to 08:00, wrapping around midnight when `end` is before `start`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+/// Glyphs in front of tasks, showing their state without relying on color.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskIcons {
+    pub done: Cow<'static, str>,
+    pub todo: Cow<'static, str>,
+    /// The selected task while working on it
+    pub active: Cow<'static, str>,
+    /// The selected task during breaks
+    pub paused: Cow<'static, str>,
+}
+
+impl Default for TaskIcons {
+    fn default() -> Self {
+        DEFAULT_CONFIG.task_icons
+    }
+}
+
+/// Which panes of the timer tab are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaneLayout {
+    Both,
+    /// Full screen timer, hiding the task list
+    TimerOnly,
+    /// Task management mode, hiding the timer
+    TasksOnly,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub pomodoro_length: Duration,
+    pub break_length: Duration,
+    /// Length of the break after a full cycle of pomodoros
+    pub long_break_length: Duration,
+    /// Number of pomodoros before a long break
+    pub pomodoros_per_cycle: u32,
+    /// Turn on the OS do-not-disturb mode while working
+    pub do_not_disturb: bool,
+    /// URLs to post transitions to
+    pub webhooks: Vec<webhook::Webhook>,
+    /// Shell commands to run on transitions
+    pub hooks: hooks::Hooks,
+    /// How to play the alarm: `rusty_audio`, `rodio` or `none`
+    pub audio_backend: AudioBackend,
+    /// How long the alarm takes to get to full volume, only with the `rodio` backend
+    pub alarm_ramp_up: Duration,
+    /// Flash the screen on transitions: `off`, `augment` or `replace` the alarm
+    pub visual_bell: VisualBell,
+    /// Times of day without sounds or desktop notifications, only visual cues
+    pub quiet_hours: Vec<QuietHours>,
+    /// Play sounds, or start every session muted
+    pub sound: bool,
+    /// Show desktop notifications
+    pub notifications: bool,
+    /// Which panes the timer tab shows
+    pub layout: PaneLayout,
+    /// Colors to use: `default`, `gruvbox`, `solarized-dark`, `solarized-light`, `high-contrast`
+    /// or `colorblind`
+    pub theme: ThemeName,
+    /// What to fill gauges with: `blocks`, `ascii` or `braille`
+    pub gauge_glyphs: GaugeGlyphs,
+    /// Show the percentage in the timer's gauge
+    pub gauge_percentage: bool,
+    /// Fill the whole screen during breaks, until a key is pressed
+    pub break_takeover: bool,
+    /// File of messages, one per line, to show in turn at the start of each pomodoro
+    pub quotes_file: Option<PathBuf>,
+    /// Move on to the next incomplete task when completing one
+    pub auto_advance: bool,
+    /// todo.txt file to take the tasks from and mark them done in
+    pub todo_txt: Option<PathBuf>,
+    /// Todoist account to take today's tasks from and close them in
+    pub todoist: Option<todoist::Todoist>,
+    /// What to do with GitHub issues taken as tasks with `--github`
+    pub github: github::GitHub,
+    /// JIRA site to take the issues from with `--jira`, and log pomodoros on
+    pub jira: Option<jira::Jira>,
+    /// Slack account to set the status of and snooze while working
+    pub slack: Option<slack::Slack>,
+    /// Discord application to show the timer as Rich Presence of
+    pub discord: Option<discord::Discord>,
+    /// CalDAV calendar to block out pomodoros on
+    pub calendar: Option<calendar::Calendar>,
+    /// Toggl or Clockify workspace to send pomodoros to as time entries
+    pub time_tracking: Option<time_tracking::TimeTracking>,
+    /// Add each pomodoro to the commit template of the git checkout it was worked in
+    pub git_commit_template: bool,
+    /// Obsidian vault to log pomodoros in the daily note of
+    pub obsidian: Option<obsidian::Obsidian>,
+    /// MQTT broker to publish the timer to, for Home Assistant
+    pub mqtt: Option<mqtt::Mqtt>,
+    /// Distracting sites to block while working
+    pub block: Option<distractions::Block>,
+    /// Stream Decks and MIDI controllers to take commands from
+    pub inputs: Vec<input::Input>,
+    /// i3 or sway commands to run when work starts and stops
+    pub i3: Option<i3::I3>,
+    /// Keep the status in `status.json` and `status.txt`
+    pub status_file: bool,
+    /// Pushover and Telegram accounts to send transitions to
+    pub push: Vec<push::Push>,
+    /// Slack channel or Matrix room to announce focus sessions in
+    pub announce: Option<announce::Announce>,
+    /// Offer the timer as a media player over MPRIS, for media keys and widgets
+    pub mpris: bool,
+    /// Offer the timer on D-Bus as gnome-pomodoro does, for its extensions and scripts
+    pub gnome_pomodoro: bool,
+    /// Who may call the HTTP API served with `--listen`
+    pub http: http::Access,
+    pub task_icons: TaskIcons,
+}
+
+pub const DEFAULT_CONFIG: Config = Config {
+    pomodoro_length: Duration::from_secs(25 * 60),
+    break_length: Duration::from_secs(5 * 60),
+    long_break_length: Duration::from_secs(15 * 60),
+    pomodoros_per_cycle: 4,
+    do_not_disturb: false,
+    webhooks: Vec::new(),
+    hooks: hooks::Hooks {
+        on_work_start: None,
+        on_work_end: None,
+        on_break_start: None,
+        on_break_end: None,
+    },
+    audio_backend: AudioBackend::RustyAudio,
+    alarm_ramp_up: Duration::ZERO,
+    visual_bell: VisualBell::Off,
+    quiet_hours: Vec::new(),
+    sound: true,
+    notifications: true,
+    layout: PaneLayout::Both,
+    theme: ThemeName::Default,
+    gauge_glyphs: GaugeGlyphs::Blocks,
+    gauge_percentage: true,
+    break_takeover: false,
+    quotes_file: None,
+    auto_advance: false,
+    todo_txt: None,
+    todoist: None,
+    github: github::GitHub {
+        token: None,
+        comment: false,
+        label: None,
+    },
+    jira: None,
+    slack: None,
+    discord: None,
+    calendar: None,
+    time_tracking: None,
+    git_commit_template: false,
+    obsidian: None,
+    mqtt: None,
+    block: None,
+    inputs: Vec::new(),
+    i3: None,
+    status_file: false,
+    push: Vec::new(),
+    announce: None,
+    mpris: false,
+    gnome_pomodoro: false,
+    http: http::Access {
+        allowed_origins: Vec::new(),
+        token: None,
+    },
+    task_icons: TaskIcons {
+        done: Cow::Borrowed("✔"),
+        todo: Cow::Borrowed("◻"),
+        active: Cow::Borrowed("▶"),
+        paused: Cow::Borrowed("⏸"),
+    },
+};
+
+/// Set a single value in the config file, leaving the rest as the user wrote it. Unlike saving
+/// the whole `Config`, this doesn't write back overrides from the command line.
+pub fn save_config_value<T: Serialize>(config_path: &Path, key: &str, value: &T) -> io::Result<()> {
+    let mut config: serde_json::Value = match fs::read_to_string(config_path) {
+        Ok(config_file) => serde_json::from_str(&config_file)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => serde_json::json!({}),
+        Err(e) => return Err(e),
+    };
+    if let Some(config) = config.as_object_mut() {
+        config.insert(key.to_string(), serde_json::to_value(value)?);
+    }
+    fs::write(config_path, serde_json::to_string_pretty(&config)?)
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
@@ -0,0 +1,205 @@
+//! Commands that can drive the app from outside the key handling, e.g. from notification
+//! actions or other processes through the control socket.
+
+use crate::{history::Record, timer::AppState};
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    /// End the work period and start the break straight away
+    StartBreak,
+    /// Make the current period longer
+    AddTime(Duration),
+    /// Add a task to the list, e.g. `Write report ~3`
+    AddTask(String),
+    /// Stop the clock until resumed
+    Pause,
+    Resume,
+    /// Pause, or resume if paused
+    TogglePause,
+    /// End the current period straight away
+    Skip,
+    /// Quit, keeping the work done so far like quitting from the TUI does
+    Stop,
+    /// Mark the selected task as done or not done
+    ToggleTask,
+    /// Add work done away from the timer to the history
+    Log(Record),
+}
+
+/// The sending half is handed out to whatever wants to control the app, the receiving half is
+/// drained by the main loop.
+pub fn channel() -> (Sender<Command>, Receiver<Command>) {
+    mpsc::channel()
+}
+
+/// Where the running instance listens for commands.
+pub fn socket_path(pomors_dir: &Path) -> PathBuf {
+    pomors_dir.join("pomors.sock")
+}
+
+/// What the running instance is doing, for other processes to show.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Status {
+    pub state: AppState,
+    /// Whether the break is the long one at the end of a cycle
+    #[serde(default)]
+    pub long_break: bool,
+    pub paused: bool,
+    pub remaining_secs: u64,
+    /// Length of the current period
+    pub length_secs: u64,
+    pub task: Option<String>,
+}
+
+/// The latest status, kept up to date by the main loop for the socket to answer with.
+pub type SharedStatus = Arc<Mutex<Option<Status>>>;
+
+/// A task as other processes see it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStatus {
+    pub name: String,
+    pub done: bool,
+    pub selected: bool,
+    /// Time worked on the task, including the ongoing period
+    pub tracked_secs: i64,
+    pub estimate: Option<u32>,
+}
+
+/// The task list in the order shown, kept up to date like `SharedStatus`.
+pub type SharedTasks = Arc<Mutex<Vec<TaskStatus>>>;
+
+/// Questions the socket answers, as opposed to commands it just carries out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Query {
+    /// Answered with a `Status` line
+    Status,
+}
+
+/// Accept commands on the socket at `path` in the background, one JSON command per line, e.g.
+/// `"start_break"` or `{"add_time":{"secs":300,"nanos":0}}`, and pass them on to `commands`.
+/// Each line is answered with a line: `"status"` with the latest `status`, commands with
+/// `{"ok":true}` and anything else with `{"error":"..."}`.
+#[cfg(unix)]
+pub fn listen(path: &Path, commands: Sender<Command>, status: SharedStatus) -> io::Result<()> {
+    use serde_json::json;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::net::{UnixListener, UnixStream},
+        thread,
+    };
+
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            "pomors is already running, `pomors attach` shows it",
+        ));
+    }
+    // Left behind by an instance that didn't get to clean up
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let commands = commands.clone();
+            let status = status.clone();
+            thread::spawn(move || {
+                let mut writer = &stream;
+                for line in BufReader::new(&stream).lines().map_while(Result::ok) {
+                    let reply = if let Ok(command) = serde_json::from_str(&line) {
+                        match commands.send(command) {
+                            Ok(()) => json!({ "ok": true }),
+                            Err(_) => json!({ "error": "pomors is quitting" }),
+                        }
+                    } else if let Ok(Query::Status) = serde_json::from_str(&line) {
+                        let status = status.lock().map(|status| status.clone());
+                        json!(status.ok().flatten())
+                    } else {
+                        json!({ "error": format!("unknown command: {line}") })
+                    };
+                    if writeln!(writer, "{reply}").is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn listen(_path: &Path, _commands: Sender<Command>, _status: SharedStatus) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the control socket needs a Unix system",
+    ))
+}
+
+/// Send `command` to the instance listening at `path`. Returns whether one was listening, and
+/// fails if it didn't take the command.
+#[cfg(unix)]
+pub fn send(path: &Path, command: &Command) -> io::Result<bool> {
+    use serde_json::Value;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::Shutdown,
+        os::unix::net::UnixStream,
+    };
+
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return Ok(false);
+    };
+    writeln!(stream, "{}", serde_json::to_string(command)?)?;
+    stream.shutdown(Shutdown::Write)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    // Versions before replies were added just close the connection
+    if line.is_empty() {
+        return Ok(true);
+    }
+    let reply: Value = serde_json::from_str(&line)?;
+    match reply.get("error").and_then(Value::as_str) {
+        Some(error) => Err(io::Error::other(error.to_string())),
+        None => Ok(true),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn send(_path: &Path, _command: &Command) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// Ask the instance listening at `path` for its status, which is `None` when nothing is running.
+#[cfg(unix)]
+pub fn query_status(path: &Path) -> io::Result<Option<Status>> {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::Shutdown,
+        os::unix::net::UnixStream,
+    };
+
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return Ok(None);
+    };
+    writeln!(stream, "{}", serde_json::to_string(&Query::Status)?)?;
+    stream.shutdown(Shutdown::Write)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(not(unix))]
+pub fn query_status(_path: &Path) -> io::Result<Option<Status>> {
+    Ok(None)
+}
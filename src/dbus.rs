@@ -0,0 +1,922 @@
+//! Just enough of the D-Bus wire protocol to offer services on the session bus: connecting,
+//! taking a name, answering method calls and sending signals.
+//!
+//! See https://dbus.freedesktop.org/doc/dbus-specification.html for the format.
+
+use std::{
+    env,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// A value of any of the types used here, with its D-Bus type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+    ObjectPath(String),
+    Signature(String),
+    Variant(Box<Value>),
+    /// Items all of the `element` signature
+    Array {
+        element: String,
+        items: Vec<Value>,
+    },
+    /// An item of a dictionary, which is an array of these
+    DictEntry(Box<Value>, Box<Value>),
+    Struct(Vec<Value>),
+}
+
+impl Value {
+    pub fn signature(&self) -> String {
+        match self {
+            Value::Byte(_) => "y".into(),
+            Value::Bool(_) => "b".into(),
+            Value::I32(_) => "i".into(),
+            Value::U32(_) => "u".into(),
+            Value::I64(_) => "x".into(),
+            Value::U64(_) => "t".into(),
+            Value::F64(_) => "d".into(),
+            Value::Str(_) => "s".into(),
+            Value::ObjectPath(_) => "o".into(),
+            Value::Signature(_) => "g".into(),
+            Value::Variant(_) => "v".into(),
+            Value::Array { element, .. } => format!("a{element}"),
+            Value::DictEntry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+            Value::Struct(fields) => format!(
+                "({})",
+                fields.iter().map(Value::signature).collect::<String>()
+            ),
+        }
+    }
+
+    pub fn str(s: &str) -> Value {
+        Value::Str(s.to_string())
+    }
+
+    pub fn variant(value: Value) -> Value {
+        Value::Variant(Box::new(value))
+    }
+
+    /// An `a{sv}` dictionary, as properties are passed around in.
+    pub fn dict(entries: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+        Value::Array {
+            element: "{sv}".into(),
+            items: entries
+                .into_iter()
+                .map(|(key, value)| {
+                    Value::DictEntry(Box::new(Value::str(key)), Box::new(Value::variant(value)))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn strings(strings: &[&str]) -> Value {
+        Value::Array {
+            element: "s".into(),
+            items: strings.iter().map(|s| Value::str(s)).collect(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) | Value::ObjectPath(s) | Value::Signature(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Value::U32(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+pub const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+pub const INTROSPECTABLE: &str = "org.freedesktop.DBus.Introspectable";
+pub const PEER: &str = "org.freedesktop.DBus.Peer";
+
+/// Set on method calls whose caller doesn't want an answer.
+const NO_REPLY_EXPECTED: u8 = 0x1;
+
+/// Longest message the spec allows, so a broken peer can't make us allocate more.
+const MAX_MESSAGE_LENGTH: usize = 128 * 1024 * 1024;
+/// How deep containers may nest, 32 arrays and 32 structs in the spec.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub kind: Kind,
+    pub flags: u8,
+    /// Set when the message is sent
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<Value>,
+}
+
+impl Message {
+    fn new(kind: Kind) -> Message {
+        Message {
+            kind,
+            flags: 0,
+            serial: 0,
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            body: Vec::new(),
+        }
+    }
+
+    pub fn method_call(
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        body: Vec<Value>,
+    ) -> Message {
+        Message {
+            destination: Some(destination.into()),
+            path: Some(path.into()),
+            interface: Some(interface.into()),
+            member: Some(member.into()),
+            body,
+            ..Message::new(Kind::MethodCall)
+        }
+    }
+
+    pub fn signal(path: &str, interface: &str, member: &str, body: Vec<Value>) -> Message {
+        Message {
+            path: Some(path.into()),
+            interface: Some(interface.into()),
+            member: Some(member.into()),
+            body,
+            ..Message::new(Kind::Signal)
+        }
+    }
+
+    pub fn method_return(call: &Message, body: Vec<Value>) -> Message {
+        Message {
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            body,
+            ..Message::new(Kind::MethodReturn)
+        }
+    }
+
+    pub fn error(call: &Message, name: &str, text: &str) -> Message {
+        Message {
+            error_name: Some(name.into()),
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            body: vec![Value::str(text)],
+            ..Message::new(Kind::Error)
+        }
+    }
+
+    /// Answer a call to `org.freedesktop.DBus.Properties`, where `properties` lists those of an
+    /// interface. They're all read only.
+    pub fn properties_reply(
+        call: &Message,
+        properties: impl Fn(&str) -> Vec<(&'static str, Value)>,
+    ) -> Message {
+        let interface = call
+            .body
+            .first()
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        match call.member.as_deref() {
+            Some("Get") => {
+                let name = call.body.get(1).and_then(Value::as_str);
+                let value = properties(interface)
+                    .into_iter()
+                    .find(|(property, _)| Some(*property) == name);
+                match value {
+                    Some((_, value)) => Message::method_return(call, vec![Value::variant(value)]),
+                    None => Message::error(
+                        call,
+                        "org.freedesktop.DBus.Error.UnknownProperty",
+                        "No such property",
+                    ),
+                }
+            }
+            Some("GetAll") => {
+                Message::method_return(call, vec![Value::dict(properties(interface))])
+            }
+            _ => Message::error(
+                call,
+                "org.freedesktop.DBus.Error.PropertyReadOnly",
+                "The properties are read only",
+            ),
+        }
+    }
+
+    /// Signal that properties of `interface` changed to the values in `changed`.
+    pub fn properties_changed(
+        path: &str,
+        interface: &str,
+        changed: Vec<(&'static str, Value)>,
+    ) -> Message {
+        Message::signal(
+            path,
+            PROPERTIES,
+            "PropertiesChanged",
+            vec![
+                Value::str(interface),
+                Value::dict(changed),
+                Value::strings(&[]),
+            ],
+        )
+    }
+
+    pub fn wants_reply(&self) -> bool {
+        self.kind == Kind::MethodCall && self.flags & NO_REPLY_EXPECTED == 0
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut fields = Vec::new();
+        let mut field = |code: u8, value: Value| {
+            fields.push(Value::Struct(vec![
+                Value::Byte(code),
+                Value::variant(value),
+            ]));
+        };
+        if let Some(path) = &self.path {
+            field(1, Value::ObjectPath(path.clone()));
+        }
+        if let Some(interface) = &self.interface {
+            field(2, Value::str(interface));
+        }
+        if let Some(member) = &self.member {
+            field(3, Value::str(member));
+        }
+        if let Some(error_name) = &self.error_name {
+            field(4, Value::str(error_name));
+        }
+        if let Some(reply_serial) = self.reply_serial {
+            field(5, Value::U32(reply_serial));
+        }
+        if let Some(destination) = &self.destination {
+            field(6, Value::str(destination));
+        }
+        let signature: String = self.body.iter().map(Value::signature).collect();
+        if !signature.is_empty() {
+            field(8, Value::Signature(signature));
+        }
+
+        // Alignment is counted from the start of the message, and the body starts 8 aligned, so
+        // it can be written on its own.
+        let mut body = Writer::default();
+        for value in &self.body {
+            body.write(value);
+        }
+
+        let mut message = Writer::default();
+        message.buf.extend([b'l', self.kind as u8, self.flags, 1]);
+        message.write(&Value::U32(body.buf.len() as u32));
+        message.write(&Value::U32(self.serial));
+        message.write(&Value::Array {
+            element: "(yv)".into(),
+            items: fields,
+        });
+        message.pad(8);
+        message.buf.extend(body.buf);
+        message.buf
+    }
+
+    fn decode(buf: &[u8]) -> io::Result<Message> {
+        if buf.len() < 16 {
+            return Err(invalid("message too short"));
+        }
+        let mut reader = Reader {
+            buf,
+            pos: 0,
+            little_endian: little_endian(buf[0])?,
+            depth: 0,
+        };
+        let kind = match buf[1] {
+            1 => Kind::MethodCall,
+            2 => Kind::MethodReturn,
+            3 => Kind::Error,
+            4 => Kind::Signal,
+            _ => return Err(invalid("unknown message type")),
+        };
+        let mut message = Message::new(kind);
+        message.flags = buf[2];
+        reader.pos = 8;
+        message.serial = reader.u32()?;
+        let mut signature = String::new();
+        if let Value::Array { items, .. } = reader.read("a(yv)")? {
+            for field in items {
+                let Value::Struct(field) = field else {
+                    continue;
+                };
+                let [Value::Byte(code), Value::Variant(value)] = field.as_slice() else {
+                    continue;
+                };
+                let string = value.as_str().map(String::from);
+                match code {
+                    1 => message.path = string,
+                    2 => message.interface = string,
+                    3 => message.member = string,
+                    4 => message.error_name = string,
+                    5 => message.reply_serial = value.as_u32(),
+                    6 => message.destination = string,
+                    7 => message.sender = string,
+                    8 => signature = string.unwrap_or_default(),
+                    _ => {}
+                }
+            }
+        }
+        reader.align(8)?;
+        let mut rest = signature.as_str();
+        while !rest.is_empty() {
+            let (first, others) = split_type(rest)?;
+            message.body.push(reader.read(first)?);
+            rest = others;
+        }
+        Ok(message)
+    }
+}
+
+fn invalid(error: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// Whether numbers are little endian, from the first byte of a message.
+fn little_endian(marker: u8) -> io::Result<bool> {
+    match marker {
+        b'l' => Ok(true),
+        b'B' => Ok(false),
+        _ => Err(invalid("unknown byte order")),
+    }
+}
+
+/// How values of the type starting with `code` are aligned.
+fn alignment(code: u8) -> usize {
+    match code {
+        b'y' | b'g' | b'v' => 1,
+        b'n' | b'q' => 2,
+        b'x' | b't' | b'd' | b'(' | b'{' => 8,
+        _ => 4,
+    }
+}
+
+/// Split the first complete type off a signature.
+fn split_type(signature: &str) -> io::Result<(&str, &str)> {
+    if !signature.is_ascii() {
+        return Err(invalid("signature isn't ASCII"));
+    }
+    let bytes = signature.as_bytes();
+    let mut end = 0;
+    // Array markers, then a single type
+    while bytes.get(end) == Some(&b'a') {
+        end += 1;
+    }
+    match bytes.get(end) {
+        Some(b'(') | Some(b'{') => {
+            let mut depth = 0;
+            loop {
+                match bytes.get(end) {
+                    Some(b'(') | Some(b'{') => depth += 1,
+                    Some(b')') | Some(b'}') => depth -= 1,
+                    Some(_) => {}
+                    None => return Err(invalid("unbalanced signature")),
+                }
+                end += 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+        Some(_) => end += 1,
+        None => return Err(invalid("incomplete signature")),
+    }
+    Ok(signature.split_at(end))
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn pad(&mut self, alignment: usize) {
+        while !self.buf.len().is_multiple_of(alignment) {
+            self.buf.push(0);
+        }
+    }
+
+    fn write(&mut self, value: &Value) {
+        self.pad(alignment(value.signature().as_bytes()[0]));
+        match value {
+            Value::Byte(n) => self.buf.push(*n),
+            Value::Bool(b) => self.buf.extend(u32::from(*b).to_le_bytes()),
+            Value::I32(n) => self.buf.extend(n.to_le_bytes()),
+            Value::U32(n) => self.buf.extend(n.to_le_bytes()),
+            Value::I64(n) => self.buf.extend(n.to_le_bytes()),
+            Value::U64(n) => self.buf.extend(n.to_le_bytes()),
+            Value::F64(n) => self.buf.extend(n.to_le_bytes()),
+            Value::Str(s) | Value::ObjectPath(s) => {
+                self.buf.extend((s.len() as u32).to_le_bytes());
+                self.buf.extend(s.as_bytes());
+                self.buf.push(0);
+            }
+            Value::Signature(s) => {
+                self.buf.push(s.len() as u8);
+                self.buf.extend(s.as_bytes());
+                self.buf.push(0);
+            }
+            Value::Variant(value) => {
+                self.write(&Value::Signature(value.signature()));
+                self.write(value);
+            }
+            Value::Array { element, items } => {
+                let length_at = self.buf.len();
+                self.buf.extend([0; 4]);
+                // The padding before the first item doesn't count towards the length
+                self.pad(alignment(element.as_bytes()[0]));
+                let start = self.buf.len();
+                for item in items {
+                    self.write(item);
+                }
+                let length = (self.buf.len() - start) as u32;
+                self.buf[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+            }
+            Value::DictEntry(key, value) => {
+                self.write(key);
+                self.write(value);
+            }
+            Value::Struct(fields) => {
+                for field in fields {
+                    self.write(field);
+                }
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+    /// How many values the one being read is inside of
+    depth: usize,
+}
+
+impl Reader<'_> {
+    fn align(&mut self, alignment: usize) -> io::Result<()> {
+        self.pos = self.pos.next_multiple_of(alignment);
+        if self.pos > self.buf.len() {
+            return Err(invalid("message too short"));
+        }
+        Ok(())
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| invalid("message too short"))?;
+        self.pos += N;
+        let mut array: [u8; N] = bytes.try_into().expect("N bytes");
+        if !self.little_endian {
+            array.reverse();
+        }
+        Ok(array)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.align(4)?;
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn string(&mut self, length: usize) -> io::Result<String> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + length)
+            .ok_or_else(|| invalid("message too short"))?;
+        // and the nul after it
+        self.pos += length + 1;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("string isn't UTF-8"))
+    }
+
+    /// Read a value of the single complete type `signature`.
+    fn read(&mut self, signature: &str) -> io::Result<Value> {
+        if self.depth == MAX_DEPTH {
+            return Err(invalid("values nested too deeply"));
+        }
+        self.depth += 1;
+        let value = self.read_value(signature);
+        self.depth -= 1;
+        value
+    }
+
+    fn read_value(&mut self, signature: &str) -> io::Result<Value> {
+        let (single, rest) = split_type(signature)?;
+        if !rest.is_empty() {
+            return Err(invalid("more than one type in a single type signature"));
+        }
+        let code = single.as_bytes()[0];
+        self.align(alignment(code))?;
+        Ok(match code {
+            b'y' => Value::Byte(self.bytes::<1>()?[0]),
+            b'b' => Value::Bool(self.u32()? != 0),
+            b'n' => Value::I32(i16::from_le_bytes(self.bytes()?).into()),
+            b'q' => Value::U32(u16::from_le_bytes(self.bytes()?).into()),
+            b'i' => Value::I32(i32::from_le_bytes(self.bytes()?)),
+            b'u' | b'h' => Value::U32(self.u32()?),
+            b'x' => Value::I64(i64::from_le_bytes(self.bytes()?)),
+            b't' => Value::U64(u64::from_le_bytes(self.bytes()?)),
+            b'd' => Value::F64(f64::from_le_bytes(self.bytes()?)),
+            b's' | b'o' => {
+                let length = self.u32()? as usize;
+                let s = self.string(length)?;
+                if code == b's' {
+                    Value::Str(s)
+                } else {
+                    Value::ObjectPath(s)
+                }
+            }
+            b'g' => {
+                let length = self.bytes::<1>()?[0] as usize;
+                Value::Signature(self.string(length)?)
+            }
+            b'v' => {
+                let length = self.bytes::<1>()?[0] as usize;
+                let signature = self.string(length)?;
+                Value::variant(self.read(&signature)?)
+            }
+            b'a' => {
+                // `split_type` made sure there's a whole type after the `a`
+                let element = &signature[1..];
+                let length = self.u32()? as usize;
+                self.align(alignment(element.as_bytes()[0]))?;
+                let end = self.pos + length;
+                let mut items = Vec::new();
+                while self.pos < end {
+                    items.push(self.read(element)?);
+                }
+                Value::Array {
+                    element: element.to_string(),
+                    items,
+                }
+            }
+            b'(' | b'{' => {
+                let close = if code == b'(' { ')' } else { '}' };
+                let mut rest = signature[1..]
+                    .strip_suffix(close)
+                    .filter(|fields| !fields.is_empty())
+                    .ok_or_else(|| invalid("malformed struct signature"))?;
+                let mut fields = Vec::new();
+                while !rest.is_empty() {
+                    let (first, others) = split_type(rest)?;
+                    fields.push(self.read(first)?);
+                    rest = others;
+                }
+                if code == b'{' && fields.len() == 2 {
+                    let value = fields.pop().expect("two fields");
+                    let key = fields.pop().expect("two fields");
+                    Value::DictEntry(Box::new(key), Box::new(value))
+                } else {
+                    Value::Struct(fields)
+                }
+            }
+            _ => return Err(invalid("unsupported type")),
+        })
+    }
+}
+
+/// A connection to the bus. Clones share it, so one thread can wait for calls while others send
+/// signals.
+#[derive(Clone)]
+pub struct Connection {
+    stream: Arc<UnixStream>,
+    /// Serial of the last message sent, locked while sending so messages don't interleave
+    serial: Arc<Mutex<u32>>,
+}
+
+impl Connection {
+    /// Connect to the session bus and authenticate as the current user.
+    pub fn session() -> io::Result<Connection> {
+        let mut stream = connect_session()?;
+        authenticate(&mut stream)?;
+        let connection = Connection {
+            stream: Arc::new(stream),
+            serial: Arc::new(Mutex::new(0)),
+        };
+        connection.call(bus_call("Hello", Vec::new()))?;
+        Ok(connection)
+    }
+
+    /// Take `name` on the bus, failing if someone else has it.
+    pub fn request_name(&self, name: &str) -> io::Result<()> {
+        const DO_NOT_QUEUE: u32 = 0x4;
+        const PRIMARY_OWNER: u32 = 1;
+        const ALREADY_OWNER: u32 = 4;
+        let reply = self.call(bus_call(
+            "RequestName",
+            vec![Value::str(name), Value::U32(DO_NOT_QUEUE)],
+        ))?;
+        match reply.body.first().and_then(Value::as_u32) {
+            Some(PRIMARY_OWNER | ALREADY_OWNER) => Ok(()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{name} is taken on the session bus"),
+            )),
+        }
+    }
+
+    pub fn send(&self, mut message: Message) -> io::Result<()> {
+        let mut serial = self
+            .serial
+            .lock()
+            .map_err(|_| io::Error::other("connection poisoned"))?;
+        *serial += 1;
+        message.serial = *serial;
+        (&*self.stream).write_all(&message.encode())
+    }
+
+    /// Send a method call and wait for its reply, dropping anything that comes in before it.
+    /// Only for setting up, or on connections that don't `serve`, as nothing else may be listening.
+    pub fn call(&self, message: Message) -> io::Result<Message> {
+        self.send(message)?;
+        let serial = *self
+            .serial
+            .lock()
+            .map_err(|_| io::Error::other("connection poisoned"))?;
+        loop {
+            let reply = self.receive()?;
+            if reply.reply_serial != Some(serial) {
+                continue;
+            }
+            return match reply.kind {
+                Kind::Error => Err(io::Error::other(format!(
+                    "{}: {}",
+                    reply.error_name.unwrap_or_default(),
+                    reply
+                        .body
+                        .first()
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                ))),
+                _ => Ok(reply),
+            };
+        }
+    }
+
+    /// Answer method calls in the background. `answer` gives the reply, or `None` for calls that
+    /// aren't to our objects.
+    pub fn serve(&self, answer: impl Fn(&Message) -> Option<Message> + Send + 'static) {
+        let connection = self.clone();
+        thread::spawn(move || {
+            while let Ok(call) = connection.receive() {
+                if let Some(reply) = answer(&call) {
+                    if call.wants_reply() {
+                        let _ = connection.send(reply);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Wait for the next message.
+    pub fn receive(&self) -> io::Result<Message> {
+        let mut stream = &*self.stream;
+        let mut fixed = [0; 16];
+        stream.read_exact(&mut fixed)?;
+        let little_endian = little_endian(fixed[0])?;
+        let number = |bytes: &[u8]| {
+            let bytes: [u8; 4] = bytes.try_into().expect("4 bytes");
+            if little_endian {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            }
+        };
+        let body_length = number(&fixed[4..8]) as usize;
+        let fields_length = number(&fixed[12..16]) as usize;
+        // Checked one at a time first, so adding them up can't overflow
+        if body_length > MAX_MESSAGE_LENGTH || fields_length > MAX_MESSAGE_LENGTH {
+            return Err(invalid("message too long"));
+        }
+        let header_length = (16 + fields_length).next_multiple_of(8);
+        if header_length + body_length > MAX_MESSAGE_LENGTH {
+            return Err(invalid("message too long"));
+        }
+        let mut buf = fixed.to_vec();
+        buf.resize(header_length + body_length, 0);
+        stream.read_exact(&mut buf[16..])?;
+        Message::decode(&buf)
+    }
+}
+
+fn bus_call(member: &str, body: Vec<Value>) -> Message {
+    Message::method_call(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+        member,
+        body,
+    )
+}
+
+fn connect_session() -> io::Result<UnixStream> {
+    let address = env::var("DBUS_SESSION_BUS_ADDRESS").unwrap_or_else(|_| {
+        let runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or_default();
+        format!("unix:path={runtime_dir}/bus")
+    });
+    // Addresses to try in turn, like `unix:path=/run/user/1000/bus,guid=...`
+    for address in address.split(';') {
+        let Some(options) = address.strip_prefix("unix:") else {
+            continue;
+        };
+        for option in options.split(',') {
+            if let Some(path) = option.strip_prefix("path=") {
+                if let Ok(stream) = UnixStream::connect(unescape(path)) {
+                    return Ok(stream);
+                }
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(name) = option.strip_prefix("abstract=") {
+                use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+                let address = SocketAddr::from_abstract_name(unescape(name))?;
+                if let Ok(stream) = UnixStream::connect_addr(&address) {
+                    return Ok(stream);
+                }
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "couldn't connect to the session bus",
+    ))
+}
+
+/// Undo the `%xx` escapes of an address value.
+fn unescape(value: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = value.as_bytes();
+    while let Some((&byte, others)) = rest.split_first() {
+        match (byte, others.get(..2)) {
+            (b'%', Some(hex)) => {
+                let hex = std::str::from_utf8(hex).unwrap_or_default();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                rest = &others[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = others;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// The EXTERNAL mechanism: the bus checks the user ID we claim against the socket's credentials.
+fn authenticate(stream: &mut UnixStream) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = std::fs::metadata("/proc/self")?.uid().to_string();
+    let hex: String = uid.bytes().map(|byte| format!("{byte:02x}")).collect();
+    stream.write_all(format!("\0AUTH EXTERNAL {hex}\r\n").as_bytes())?;
+    let mut line = Vec::new();
+    let mut byte = [0];
+    while !line.ends_with(b"\r\n") {
+        stream.read_exact(&mut byte)?;
+        line.push(byte[0]);
+    }
+    if !line.starts_with(b"OK ") {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the session bus didn't accept us",
+        ));
+    }
+    stream.write_all(b"BEGIN\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal() -> Message {
+        Message::signal(
+            "/org/example",
+            "org.example.Timer",
+            "Changed",
+            vec![
+                Value::dict([("Task", Value::str("write tests"))]),
+                Value::U32(7),
+            ],
+        )
+    }
+
+    /// A signal with `body` as its body, said to be of type `signature`.
+    fn raw(signature: &str, body: &[u8]) -> Vec<u8> {
+        let mut message = Writer::default();
+        message.buf.extend([b'l', Kind::Signal as u8, 0, 1]);
+        message.write(&Value::U32(body.len() as u32));
+        message.write(&Value::U32(1));
+        message.write(&Value::Array {
+            element: "(yv)".into(),
+            items: vec![Value::Struct(vec![
+                Value::Byte(8),
+                Value::variant(Value::Signature(signature.into())),
+            ])],
+        });
+        message.pad(8);
+        message.buf.extend(body);
+        message.buf
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let decoded = Message::decode(&signal().encode()).unwrap();
+        assert_eq!(decoded.kind, Kind::Signal);
+        assert_eq!(decoded.member.as_deref(), Some("Changed"));
+        assert_eq!(decoded.body, signal().body);
+    }
+
+    #[test]
+    fn truncated_messages_are_invalid() {
+        let encoded = signal().encode();
+        for length in 0..encoded.len() {
+            assert!(
+                Message::decode(&encoded[..length]).is_err(),
+                "decoded the first {length} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_messages_are_invalid() {
+        let mut unknown_byte_order = signal().encode();
+        unknown_byte_order[0] = b'x';
+        let malformed = [
+            unknown_byte_order,
+            // Variants of no type, an array of nothing, an empty struct and an unclosed one
+            raw("v", &[0, 0]),
+            raw("v", &[1, b'a', 0]),
+            raw("v", &[2, b'(', b')', 0]),
+            raw("v", &[2, b'(', b'y', 0]),
+            raw("v", "\u{e9}\0".as_bytes()),
+            raw("(y", &[0]),
+            raw("()", &[]),
+            raw("a", &[0; 4]),
+        ];
+        for message in malformed {
+            assert!(Message::decode(&message).is_err(), "decoded {message:?}");
+        }
+    }
+
+    #[test]
+    fn deeply_nested_variants_are_invalid() {
+        let nested = |depth| {
+            let mut body = [1, b'v', 0].repeat(depth);
+            body.extend([1, b'y', 0, 5]);
+            raw("v", &body)
+        };
+        assert!(Message::decode(&nested(3)).is_ok());
+        assert!(Message::decode(&nested(MAX_DEPTH)).is_err());
+    }
+
+    #[test]
+    fn receiving_a_huge_message_fails_without_reading_it() {
+        let (ours, theirs) = UnixStream::pair().unwrap();
+        let connection = Connection {
+            stream: Arc::new(ours),
+            serial: Arc::new(Mutex::new(0)),
+        };
+        let mut header = raw("", &[]);
+        header[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        (&theirs).write_all(&header).unwrap();
+        let error = connection.receive().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,165 @@
+//! Showing the timer as Discord Rich Presence, through the IPC socket of the Discord client.
+//!
+//! The client may not be running, or be started or quit while the timer runs, so the connection
+//! is made when there's something to show and made again after it breaks.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use crate::{control::Status, timer::AppState};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Discord {
+    /// ID of an application made at https://discord.com/developers/applications, whose name is
+    /// shown as what's being played
+    pub client_id: String,
+}
+
+/// Sends the status to Discord from a thread of its own, so a slow or missing client never holds
+/// up the timer.
+pub struct Presence {
+    statuses: Sender<Status>,
+}
+
+impl Presence {
+    pub fn start(discord: &Discord) -> Presence {
+        let (statuses, received) = mpsc::channel::<Status>();
+        let client_id = discord.client_id.clone();
+        thread::spawn(move || {
+            let mut connection = None;
+            let mut shown: Option<(Status, i64)> = None;
+            for status in received {
+                let end = chrono::Utc::now().timestamp() + status.remaining_secs as i64;
+                if shown.as_ref().is_some_and(|(shown, shown_end)| {
+                    same(shown, &status) && (shown_end - end).abs() <= 2
+                }) {
+                    continue;
+                }
+                if connection.is_none() {
+                    connection = ipc::connect(&client_id).ok();
+                }
+                let Some(socket) = &mut connection else {
+                    continue;
+                };
+                match ipc::set_activity(socket, activity(&status, end)) {
+                    Ok(()) => shown = Some((status, end)),
+                    Err(_) => {
+                        connection = None;
+                        shown = None;
+                    }
+                }
+            }
+        });
+        Presence { statuses }
+    }
+
+    /// Show `status`, unless it's what's shown already.
+    pub fn update(&self, status: Status) {
+        let _ = self.statuses.send(status);
+    }
+}
+
+fn same(a: &Status, b: &Status) -> bool {
+    a.state == b.state && a.paused == b.paused && a.task == b.task
+}
+
+fn activity(status: &Status, end: i64) -> Value {
+    let state = match (status.paused, status.state) {
+        (true, _) => "Paused",
+        (false, AppState::Working) => "Focusing",
+        (false, AppState::TakingABreak) => "On a break",
+    };
+    let details = match &status.task {
+        Some(task) => format!("Working on {task}"),
+        None => "Pomodoro".to_string(),
+    };
+    let mut activity = json!({ "details": details, "state": state });
+    if !status.paused {
+        activity["timestamps"] = json!({ "end": end });
+    }
+    activity
+}
+
+#[cfg(unix)]
+mod ipc {
+    use serde_json::{json, Value};
+    use std::{
+        env,
+        io::{self, Read, Write},
+        os::unix::net::UnixStream,
+        path::PathBuf,
+        time::Duration,
+    };
+
+    const HANDSHAKE: u32 = 0;
+    const FRAME: u32 = 1;
+
+    /// Connect to the first Discord client socket that answers, and introduce ourselves.
+    pub fn connect(client_id: &str) -> io::Result<UnixStream> {
+        let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+            .into_iter()
+            .find_map(env::var_os)
+            .map_or_else(|| PathBuf::from("/tmp"), PathBuf::from);
+        let mut socket = (0..10)
+            .find_map(|i| UnixStream::connect(dir.join(format!("discord-ipc-{i}"))).ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Discord isn't running"))?;
+        socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+        send(
+            &mut socket,
+            HANDSHAKE,
+            &json!({ "v": 1, "client_id": client_id }),
+        )?;
+        receive(&mut socket)?;
+        Ok(socket)
+    }
+
+    pub fn set_activity(socket: &mut UnixStream, activity: Value) -> io::Result<()> {
+        let command = json!({
+            "cmd": "SET_ACTIVITY",
+            "args": { "pid": std::process::id(), "activity": activity },
+            "nonce": chrono::Utc::now().timestamp_millis().to_string(),
+        });
+        send(socket, FRAME, &command)?;
+        receive(socket)
+    }
+
+    /// A frame is the opcode and the length of the JSON payload, both little endian, then the
+    /// payload.
+    fn send(socket: &mut UnixStream, opcode: u32, payload: &Value) -> io::Result<()> {
+        let payload = payload.to_string();
+        let mut frame = Vec::with_capacity(8 + payload.len());
+        frame.extend(opcode.to_le_bytes());
+        frame.extend((payload.len() as u32).to_le_bytes());
+        frame.extend(payload.as_bytes());
+        socket.write_all(&frame)
+    }
+
+    /// Read the reply, which only matters in that it has to be read.
+    fn receive(socket: &mut UnixStream) -> io::Result<()> {
+        let mut header = [0; 8];
+        socket.read_exact(&mut header)?;
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        io::copy(&mut socket.take(length.into()), &mut io::sink())?;
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod ipc {
+    use serde_json::Value;
+    use std::io;
+
+    pub struct Socket;
+
+    pub fn connect(_client_id: &str) -> io::Result<Socket> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn set_activity(_socket: &mut Socket, _activity: Value) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
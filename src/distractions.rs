@@ -0,0 +1,118 @@
+//! Blocking distracting sites while working, by pointing them nowhere in the hosts file or with
+//! the user's own scripts.
+//!
+//! The block is lifted when a break starts and when pomors quits, and anything left behind by a
+//! crash is cleaned up the next time it starts.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use crate::hooks;
+
+const START_MARKER: &str = "# pomors: blocked until the break";
+const END_MARKER: &str = "# pomors: end of block";
+
+#[cfg(unix)]
+const HOSTS_FILE: &str = "/etc/hosts";
+#[cfg(windows)]
+const HOSTS_FILE: &str = r"C:\Windows\System32\drivers\etc\hosts";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Block {
+    /// Sites to block, along with their `www.` subdomain
+    pub domains: Vec<String>,
+    /// Only passed on to the commands, as `POMORS_APPS`
+    pub apps: Vec<String>,
+    /// Run to block instead of changing the hosts file, with `POMORS_DOMAINS` and `POMORS_APPS`
+    pub block_command: Option<String>,
+    /// Run to lift the block
+    pub unblock_command: Option<String>,
+    /// Another hosts file to change, for trying it out
+    pub hosts_file: Option<PathBuf>,
+}
+
+impl Block {
+    fn uses_commands(&self) -> bool {
+        self.block_command.is_some() || self.unblock_command.is_some()
+    }
+
+    fn hosts_file(&self) -> &Path {
+        self.hosts_file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(HOSTS_FILE))
+    }
+
+    pub fn block(&self) -> io::Result<()> {
+        if self.uses_commands() {
+            return self.run(self.block_command.as_deref());
+        }
+        let mut hosts = without_block(&fs::read_to_string(self.hosts_file())?);
+        if !hosts.is_empty() && !hosts.ends_with('\n') {
+            hosts.push('\n');
+        }
+        hosts += START_MARKER;
+        hosts.push('\n');
+        for domain in &self.domains {
+            let domain = domain.trim().trim_start_matches("www.");
+            hosts += &format!("0.0.0.0 {domain}\n0.0.0.0 www.{domain}\n");
+        }
+        hosts += END_MARKER;
+        hosts.push('\n');
+        fs::write(self.hosts_file(), hosts)
+    }
+
+    pub fn unblock(&self) -> io::Result<()> {
+        if self.uses_commands() {
+            return self.run(self.unblock_command.as_deref());
+        }
+        let hosts = fs::read_to_string(self.hosts_file())?;
+        let unblocked = without_block(&hosts);
+        if unblocked != hosts {
+            fs::write(self.hosts_file(), unblocked)?;
+        }
+        Ok(())
+    }
+
+    /// Run `command`, waiting for it so the block is in place, or lifted, when this returns.
+    fn run(&self, command: Option<&str>) -> io::Result<()> {
+        let Some(command) = command else {
+            return Ok(());
+        };
+        let status = hooks::shell(command)
+            .env("POMORS_DOMAINS", self.domains.join(" "))
+            .env("POMORS_APPS", self.apps.join(" "))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "`{command}` failed with {status}"
+            )))
+        }
+    }
+}
+
+/// `hosts` without the lines pomors added.
+fn without_block(hosts: &str) -> String {
+    let mut blocking = false;
+    let mut kept = String::new();
+    for line in hosts.lines() {
+        match line.trim() {
+            START_MARKER => blocking = true,
+            END_MARKER => blocking = false,
+            _ if !blocking => {
+                kept += line;
+                kept.push('\n');
+            }
+            _ => {}
+        }
+    }
+    kept
+}
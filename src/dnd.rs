@@ -0,0 +1,155 @@
+//! Toggling the operating system's do-not-disturb / focus mode.
+//!
+//! All of this is best effort: a missing tool or an unsupported desktop just leaves the
+//! notifications setting untouched.
+
+use std::process::{Command, Stdio};
+#[cfg(unix)]
+use std::{
+    env, io,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(unix)]
+use crate::dbus::{Connection, Message, Value};
+
+/// Name of the macOS Shortcuts used to turn Focus on and off. They have to be created by the user.
+const MACOS_FOCUS_ON_SHORTCUT: &str = "pomors focus on";
+const MACOS_FOCUS_OFF_SHORTCUT: &str = "pomors focus off";
+
+#[cfg(unix)]
+const NOTIFICATIONS: &str = "org.freedesktop.Notifications";
+#[cfg(unix)]
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+#[cfg(unix)]
+const GNOME_NOTIFICATIONS_SCHEMA: &str = "org.gnome.desktop.notifications";
+
+/// How notifications are being held off, to undo it with.
+#[cfg(unix)]
+enum Inhibition {
+    /// The connection the notification server was asked to hold off on, and the cookie it gave.
+    /// The server lets go when the connection closes, so it's kept open until disabled.
+    Cookie(Connection, u32),
+    /// GNOME Shell doesn't take requests to hold off, so its banners are turned off instead. This
+    /// is what the setting was before.
+    GnomeBanners(String),
+}
+
+/// Holds off desktop notifications while enabled. Clones share it, so it can be turned on and
+/// off from the background worker.
+#[derive(Clone, Default)]
+pub struct DoNotDisturb {
+    #[cfg(unix)]
+    inhibition: Arc<Mutex<Option<Inhibition>>>,
+}
+
+impl DoNotDisturb {
+    pub fn enable(&self) {
+        self.set(true)
+    }
+
+    pub fn disable(&self) {
+        self.set(false)
+    }
+
+    fn set(&self, do_not_disturb: bool) {
+        if cfg!(target_os = "macos") {
+            let shortcut = if do_not_disturb {
+                MACOS_FOCUS_ON_SHORTCUT
+            } else {
+                MACOS_FOCUS_OFF_SHORTCUT
+            };
+            run("shortcuts", &["run", shortcut]);
+            return;
+        }
+
+        #[cfg(unix)]
+        self.inhibit(do_not_disturb);
+    }
+
+    #[cfg(unix)]
+    fn inhibit(&self, do_not_disturb: bool) {
+        let Ok(mut inhibition) = self.inhibition.lock() else {
+            return;
+        };
+        match (do_not_disturb, inhibition.take()) {
+            (true, None) => {
+                let is_gnome = env::var("XDG_CURRENT_DESKTOP").is_ok_and(|d| d.contains("GNOME"));
+                *inhibition = if is_gnome {
+                    hide_gnome_banners().map(Inhibition::GnomeBanners)
+                } else {
+                    inhibit().ok()
+                };
+            }
+            (true, held) => *inhibition = held,
+            (false, Some(Inhibition::Cookie(connection, cookie))) => {
+                let _ = connection.call(notifications_call("UnInhibit", vec![Value::U32(cookie)]));
+            }
+            (false, Some(Inhibition::GnomeBanners(before))) => {
+                run(
+                    "gsettings",
+                    &["set", GNOME_NOTIFICATIONS_SCHEMA, "show-banners", &before],
+                );
+            }
+            (false, None) => {}
+        }
+    }
+}
+
+/// Ask the notification server to hold off on notifications.
+#[cfg(unix)]
+fn inhibit() -> io::Result<Inhibition> {
+    let connection = Connection::session()?;
+    let reply = connection.call(notifications_call(
+        "Inhibit",
+        vec![
+            Value::str("pomors"),
+            Value::str("Working on a pomodoro"),
+            Value::dict(Vec::new()),
+        ],
+    ))?;
+    let cookie = reply
+        .body
+        .first()
+        .and_then(Value::as_u32)
+        .ok_or_else(|| io::Error::other("Inhibit didn't return a cookie"))?;
+    Ok(Inhibition::Cookie(connection, cookie))
+}
+
+/// Turn off GNOME's notification banners, returning what the setting was.
+#[cfg(unix)]
+fn hide_gnome_banners() -> Option<String> {
+    let output = Command::new("gsettings")
+        .args(["get", GNOME_NOTIFICATIONS_SCHEMA, "show-banners"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let before = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    run(
+        "gsettings",
+        &["set", GNOME_NOTIFICATIONS_SCHEMA, "show-banners", "false"],
+    );
+    Some(before)
+}
+
+#[cfg(unix)]
+fn notifications_call(member: &str, body: Vec<Value>) -> Message {
+    Message::method_call(
+        NOTIFICATIONS,
+        NOTIFICATIONS_PATH,
+        NOTIFICATIONS,
+        member,
+        body,
+    )
+}
+
+fn run(program: &str, args: &[&str]) {
+    let _ = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
@@ -0,0 +1,74 @@
+//! Everything the app reacts to, gathered on one channel by the threads that wait for it, so a
+//! single loop can handle them one at a time.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::control::Command;
+
+/// How often watched files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum Event {
+    /// A key, click or resize in the terminal
+    Input(crossterm::event::Event),
+    /// Time to check on the timer, sent by the loop waiting for events when there weren't any
+    /// for a while
+    Tick,
+    /// A command from another process, a notification or a signal
+    Command(Command),
+    /// A file the app reads from was changed
+    FileChanged(PathBuf),
+    /// Something to tell the user, from work done in the background
+    Message(String),
+}
+
+pub fn channel() -> (Sender<Event>, Receiver<Event>) {
+    mpsc::channel()
+}
+
+/// Send what happens in the terminal. Stops when the terminal can't be read.
+pub fn terminal(events: Sender<Event>) {
+    thread::spawn(move || {
+        while let Ok(event) = crossterm::event::read() {
+            if events.send(Event::Input(event)).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// Pass on the commands sent to the app.
+pub fn forward(commands: Receiver<Command>, events: Sender<Event>) {
+    thread::spawn(move || {
+        for command in commands {
+            if events.send(Event::Command(command)).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// Send `FileChanged` whenever `path` is modified, created or removed.
+pub fn watch(path: PathBuf, events: Sender<Event>) {
+    thread::spawn(move || {
+        let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut last_modified = modified(&path);
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let now_modified = modified(&path);
+            if now_modified != last_modified {
+                last_modified = now_modified;
+                if events.send(Event::FileChanged(path.clone())).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
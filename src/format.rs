@@ -0,0 +1,87 @@
+//! Turning values into text for people to read, and text people wrote back into values.
+
+use std::{
+    num::{IntErrorKind, ParseIntError},
+    time::Duration,
+};
+
+/// `1h 25m`, `25m 30s` or `30s`, dropping the seconds once there are hours.
+pub fn duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h {mins}m")
+    } else if mins > 0 {
+        format!("{mins}m {secs}s")
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Like `duration`, for the signed durations `chrono` works with. Negative is shown as zero.
+pub fn chrono_duration(duration: chrono::Duration) -> String {
+    self::duration(duration.to_std().unwrap_or_default())
+}
+
+/// Read a duration like `25m`, `1h30m` or `90s`. A bare number is in minutes.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let too_long = || format!("\"{text}\" is too long a duration");
+    match text.parse::<u64>() {
+        Ok(mins) => {
+            return mins
+                .checked_mul(60)
+                .map(Duration::from_secs)
+                .ok_or_else(too_long)
+        }
+        Err(e) if *e.kind() == IntErrorKind::PosOverflow => return Err(too_long()),
+        Err(_) => {}
+    }
+
+    let mut secs: u64 = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("unknown unit '{c}' in \"{text}\", use h, m or s")),
+        };
+        let value: u64 = number.parse().map_err(|e: ParseIntError| match e.kind() {
+            IntErrorKind::PosOverflow => too_long(),
+            _ => format!("expected a number before '{c}' in \"{text}\""),
+        })?;
+        secs = value
+            .checked_mul(unit)
+            .and_then(|value| secs.checked_add(value))
+            .ok_or_else(too_long)?;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(format!("missing a unit after {number} in \"{text}\""));
+    }
+    if text.is_empty() {
+        return Err("expected a duration like 25m, 1h30m or 90s".to_string());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Read a time of day like `17:30`.
+pub fn parse_time_of_day(text: &str) -> Result<chrono::NaiveTime, String> {
+    chrono::NaiveTime::parse_from_str(text.trim(), "%H:%M")
+        .map_err(|_| format!("expected a time like 17:30, not \"{text}\""))
+}
+
+/// Read a date and time like `2024-05-02 14:00`, or a time like `14:00` for today.
+pub fn parse_date_time(text: &str) -> Result<chrono::NaiveDateTime, String> {
+    let text = text.trim();
+    if let Ok(time) = parse_time_of_day(text) {
+        return Ok(chrono::Local::now().date_naive().and_time(time));
+    }
+    chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M")
+        .map_err(|_| format!("expected a date and time like 2024-05-02 14:00, not \"{text}\""))
+}
@@ -0,0 +1,122 @@
+//! Progress gauges drawn with configurable glyphs, for fonts where some of them render poorly.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GaugeGlyphs {
+    /// `███   `
+    Blocks,
+    /// `###---`
+    Ascii,
+    /// `⣿⣿⣿⣀⣀⣀`
+    Braille,
+}
+
+impl GaugeGlyphs {
+    /// The filled and the empty glyph.
+    fn glyphs(self) -> (&'static str, &'static str) {
+        match self {
+            GaugeGlyphs::Blocks => ("█", " "),
+            GaugeGlyphs::Ascii => ("#", "-"),
+            GaugeGlyphs::Braille => ("⣿", "⣀"),
+        }
+    }
+
+    /// A `width` characters long bar, filled up to `ratio`.
+    pub fn bar(self, ratio: f64, width: usize) -> String {
+        let (full, empty) = self.glyphs();
+        let filled = (ratio.clamp(0.0, 1.0) * width as f64).round() as usize;
+        full.repeat(filled) + &empty.repeat(width - filled)
+    }
+}
+
+/// Like ratatui's `Gauge`, with the glyphs to fill it with.
+pub struct GlyphGauge<'a> {
+    block: Option<Block<'a>>,
+    ratio: f64,
+    label: Option<String>,
+    style: Style,
+    glyphs: GaugeGlyphs,
+}
+
+impl<'a> GlyphGauge<'a> {
+    pub fn new(glyphs: GaugeGlyphs) -> Self {
+        Self {
+            block: None,
+            ratio: 0.0,
+            label: None,
+            style: Style::default(),
+            glyphs,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn ratio(mut self, ratio: f64) -> Self {
+        self.ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl Widget for GlyphGauge<'_> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let area = match self.block.take() {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        let (full, empty) = self.glyphs.glyphs();
+        let filled = area.left() + (self.ratio * f64::from(area.width)).round() as u16;
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let glyph = if x < filled { full } else { empty };
+                buf.get_mut(x, y).set_symbol(glyph).set_style(self.style);
+            }
+        }
+
+        // The label goes in the middle, inverted where it's over the filled part
+        let Some(label) = self.label else {
+            return;
+        };
+        let width = (label.chars().count() as u16).min(area.width);
+        let left = area.left() + (area.width - width) / 2;
+        let y = area.top() + area.height / 2;
+        for (x, character) in (left..left + width).zip(label.chars()) {
+            let style = if x < filled {
+                self.style.add_modifier(Modifier::REVERSED)
+            } else {
+                self.style
+            };
+            buf.get_mut(x, y)
+                .set_symbol(&character.to_string())
+                .set_style(style);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Noting which git checkout was being worked in, and adding pomodoros to its commit template.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// A git repository and the branch checked out in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkout {
+    /// The top of the working tree
+    pub repo: PathBuf,
+    /// `HEAD` when it's detached
+    pub branch: String,
+}
+
+/// The checkout the current directory is in, if any.
+pub fn current() -> Option<Checkout> {
+    let repo = git(Path::new("."), &["rev-parse", "--show-toplevel"])?;
+    let branch = git(Path::new("."), &["symbolic-ref", "--short", "HEAD"])
+        .unwrap_or_else(|| "HEAD".to_string());
+    Some(Checkout {
+        repo: PathBuf::from(repo),
+        branch,
+    })
+}
+
+/// The output of a git command run in `dir`, `None` if it failed or git isn't installed.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    let output = String::from_utf8(output.stdout)
+        .ok()
+        .filter(|_| output.status.success())?;
+    Some(output.trim().to_string()).filter(|output| !output.is_empty())
+}
+
+impl Checkout {
+    /// Add `line` to the file set as `commit.template`, so it shows up in the next commit message.
+    /// Does nothing when there's no template.
+    pub fn append_to_template(&self, line: &str) -> io::Result<()> {
+        let Some(template) = git(
+            &self.repo,
+            &["config", "--path", "--get", "commit.template"],
+        ) else {
+            return Ok(());
+        };
+        // Git reads a relative template path from the top of the working tree
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.repo.join(template))?;
+        writeln!(file, "{line}")
+    }
+}
@@ -0,0 +1,112 @@
+//! Taking the open issues of a GitHub repository as tasks, and commenting on or labelling them
+//! when they're done.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{env, error::Error, thread, time::Duration};
+
+const API: &str = "https://api.github.com";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitHub {
+    /// Personal access token, falling back on `GITHUB_TOKEN`
+    pub token: Option<String>,
+    /// Comment on an issue with the time tracked on it when it's done
+    pub comment: bool,
+    /// Label to add to an issue when it's done, and remove when it's reopened
+    pub label: Option<String>,
+}
+
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+}
+
+impl GitHub {
+    fn token(&self) -> Option<String> {
+        self.token.clone().or_else(|| env::var("GITHUB_TOKEN").ok())
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = ureq::request(method, &format!("{API}{path}"))
+            .set("Accept", "application/vnd.github+json")
+            .timeout(TIMEOUT);
+        match self.token() {
+            Some(token) => request.set("Authorization", &format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    /// The open issues of `repo`, leaving out pull requests. `assignee` is a login, or `me` for
+    /// the owner of the token.
+    pub fn issues(&self, repo: &str, assignee: Option<&str>) -> Result<Vec<Issue>, Box<dyn Error>> {
+        let mut request = self
+            .request("GET", &format!("/repos/{repo}/issues"))
+            .query("state", "open")
+            .query("per_page", "100");
+        if let Some(assignee) = assignee {
+            let login = match assignee {
+                "me" => self.login()?,
+                login => login.to_string(),
+            };
+            request = request.query("assignee", &login);
+        }
+        let issues: Vec<Value> = request.call()?.into_json()?;
+        Ok(issues
+            .into_iter()
+            .filter(|issue| issue.get("pull_request").is_none())
+            .filter_map(|issue| {
+                Some(Issue {
+                    number: issue["number"].as_u64()?,
+                    title: issue["title"].as_str()?.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn login(&self) -> Result<String, Box<dyn Error>> {
+        if self.token().is_none() {
+            return Err("`--assignee me` needs a GitHub token".into());
+        }
+        let user: Value = self.request("GET", "/user").call()?.into_json()?;
+        Ok(user["login"]
+            .as_str()
+            .ok_or("GitHub didn't say who the token belongs to")?
+            .to_string())
+    }
+
+    /// Comment on and label the issue as configured, without waiting for the responses.
+    pub fn completed(&self, repo: &str, number: u64, tracked: &str) {
+        let issue = format!("/repos/{repo}/issues/{number}");
+        if self.comment {
+            let request = self.request("POST", &format!("{issue}/comments"));
+            let body = json!({ "body": format!("Tracked {tracked} on this in pomors.") });
+            thread::spawn(move || {
+                // The issue just goes without the comment, the pomodoro is still in the history
+                let _ = request.send_json(body);
+            });
+        }
+        if let Some(label) = &self.label {
+            let request = self.request("POST", &format!("{issue}/labels"));
+            let body = json!({ "labels": [label] });
+            thread::spawn(move || {
+                let _ = request.send_json(body);
+            });
+        }
+    }
+
+    /// Take the label off the issue again.
+    pub fn reopened(&self, repo: &str, number: u64) {
+        if let Some(label) = &self.label {
+            let request = self.request(
+                "DELETE",
+                &format!("/repos/{repo}/issues/{number}/labels/{label}"),
+            );
+            thread::spawn(move || {
+                let _ = request.call();
+            });
+        }
+    }
+}
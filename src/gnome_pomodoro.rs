@@ -0,0 +1,229 @@
+//! Offering the timer on the session bus the way [gnome-pomodoro](https://gnomepomodoro.org)
+//! does, as `org.gnome.Pomodoro`, so the shell extensions and scripts written for it work with
+//! pomors.
+
+use std::{io, sync::mpsc::Sender, thread, time::Duration};
+
+use chrono::Utc;
+
+use crate::{
+    control::{Command, SharedStatus, Status},
+    dbus::{Connection, Message, Value, INTROSPECTABLE, PEER, PROPERTIES},
+    timer::AppState,
+};
+
+const NAME: &str = "org.gnome.Pomodoro";
+const PATH: &str = "/org/gnome/Pomodoro";
+const INTERFACE: &str = "org.gnome.Pomodoro";
+/// The gnome-pomodoro version whose interface this follows
+const VERSION: &str = "0.24.0";
+
+const INTROSPECTION: &str = r#"<node>
+  <interface name="org.gnome.Pomodoro">
+    <property name="Elapsed" type="d" access="read"/>
+    <property name="State" type="s" access="read"/>
+    <property name="StateDuration" type="d" access="read"/>
+    <property name="IsPaused" type="b" access="read"/>
+    <property name="Version" type="s" access="read"/>
+    <method name="SetState">
+      <arg name="state" type="s" direction="in"/>
+      <arg name="timestamp" type="d" direction="in"/>
+    </method>
+    <method name="SetStateDuration">
+      <arg name="state" type="s" direction="in"/>
+      <arg name="duration" type="d" direction="in"/>
+    </method>
+    <method name="Start"/>
+    <method name="Stop"/>
+    <method name="Reset"/>
+    <method name="Pause"/>
+    <method name="Resume"/>
+    <method name="Skip"/>
+    <method name="ShowMainWindow">
+      <arg name="mode" type="s" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="ShowPreferences">
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="Quit"/>
+    <signal name="StateEntered">
+      <arg name="state" type="a{sv}"/>
+    </signal>
+    <signal name="StateLeft">
+      <arg name="state" type="a{sv}"/>
+    </signal>
+    <signal name="Paused"/>
+    <signal name="Resumed"/>
+  </interface>
+</node>"#;
+
+/// Take `org.gnome.Pomodoro` on the session bus, answer calls to it in the background, and signal
+/// state changes like gnome-pomodoro does.
+pub fn serve(commands: Sender<Command>, status: SharedStatus) -> io::Result<()> {
+    let connection = Connection::session()?;
+    connection.request_name(NAME)?;
+
+    let calls_status = status.clone();
+    connection.serve(move |call| answer(call, &commands, &calls_status));
+
+    thread::spawn(move || {
+        let mut last: Option<Status> = None;
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let Ok(current) = status.lock().map(|status| status.clone()) else {
+                return;
+            };
+            for signal in signals(last.as_ref(), current.as_ref()) {
+                if connection.send(signal).is_err() {
+                    return;
+                }
+            }
+            last = current;
+        }
+    });
+    Ok(())
+}
+
+/// The signals for going from `last` to `current`: leaving and entering states, pausing and
+/// resuming, and the elapsed time ticking on.
+fn signals(last: Option<&Status>, current: Option<&Status>) -> Vec<Message> {
+    let mut signals = Vec::new();
+    let state_changed = last.map(state_name) != current.map(state_name);
+    if state_changed {
+        if let Some(last) = last {
+            signals.push(Message::signal(
+                PATH,
+                INTERFACE,
+                "StateLeft",
+                vec![state_dict(last)],
+            ));
+        }
+        if let Some(current) = current {
+            signals.push(Message::signal(
+                PATH,
+                INTERFACE,
+                "StateEntered",
+                vec![state_dict(current)],
+            ));
+        }
+    }
+    let paused = current.is_some_and(|status| status.paused);
+    if !state_changed && last.is_some_and(|status| status.paused) != paused {
+        signals.push(Message::signal(
+            PATH,
+            INTERFACE,
+            if paused { "Paused" } else { "Resumed" },
+            Vec::new(),
+        ));
+    }
+    let changed: Vec<_> = properties(current)
+        .into_iter()
+        .zip(properties(last))
+        .filter(|(current, last)| current != last)
+        .map(|(current, _)| current)
+        .collect();
+    if !changed.is_empty() {
+        signals.push(Message::properties_changed(PATH, INTERFACE, changed));
+    }
+    signals
+}
+
+fn answer(call: &Message, commands: &Sender<Command>, status: &SharedStatus) -> Option<Message> {
+    if call.path.as_deref() != Some(PATH) {
+        return None;
+    }
+    let status = status.lock().ok()?.clone();
+    let command = match call.interface.as_deref()? {
+        INTERFACE => match call.member.as_deref()? {
+            "Start" | "Resume" => Some(Command::Resume),
+            "Pause" => Some(Command::Pause),
+            "Skip" => Some(Command::Skip),
+            "Stop" | "Quit" => Some(Command::Stop),
+            "SetState" => match call.body.first().and_then(Value::as_str) {
+                Some("null") => Some(Command::Stop),
+                // There are only pomodoros and breaks to go between, and the cycle decides whether
+                // a break is long
+                Some(state) if Some(state) != status.as_ref().map(state_name) => {
+                    Some(Command::Skip)
+                }
+                _ => None,
+            },
+            // Lengths are set in the config, and there are no windows to show
+            "SetStateDuration" | "Reset" | "ShowMainWindow" | "ShowPreferences" => None,
+            _ => {
+                return Some(Message::error(
+                    call,
+                    "org.freedesktop.DBus.Error.UnknownMethod",
+                    "No such method",
+                ))
+            }
+        },
+        PROPERTIES => {
+            return Some(Message::properties_reply(call, |interface| {
+                if interface == INTERFACE {
+                    properties(status.as_ref())
+                } else {
+                    Vec::new()
+                }
+            }))
+        }
+        INTROSPECTABLE => {
+            return Some(Message::method_return(
+                call,
+                vec![Value::str(INTROSPECTION)],
+            ))
+        }
+        PEER => None,
+        _ => {
+            return Some(Message::error(
+                call,
+                "org.freedesktop.DBus.Error.UnknownInterface",
+                "No such interface",
+            ))
+        }
+    };
+    if let Some(command) = command {
+        let _ = commands.send(command);
+    }
+    Some(Message::method_return(call, Vec::new()))
+}
+
+fn state_name(status: &Status) -> &'static str {
+    match status.state {
+        AppState::Working => "pomodoro",
+        AppState::TakingABreak if status.long_break => "long-break",
+        AppState::TakingABreak => "short-break",
+    }
+}
+
+fn elapsed(status: &Status) -> f64 {
+    status.length_secs.saturating_sub(status.remaining_secs) as f64
+}
+
+/// The state as the `StateEntered` and `StateLeft` signals describe it.
+fn state_dict(status: &Status) -> Value {
+    let now = Utc::now().timestamp_millis() as f64 / 1000.0;
+    Value::dict([
+        ("name", Value::str(state_name(status))),
+        ("timestamp", Value::F64(now - elapsed(status))),
+        ("elapsed", Value::F64(elapsed(status))),
+        ("duration", Value::F64(status.length_secs as f64)),
+    ])
+}
+
+fn properties(status: Option<&Status>) -> Vec<(&'static str, Value)> {
+    vec![
+        ("Elapsed", Value::F64(status.map_or(0.0, elapsed))),
+        ("State", Value::str(status.map_or("null", state_name))),
+        (
+            "StateDuration",
+            Value::F64(status.map_or(0.0, |status| status.length_secs as f64)),
+        ),
+        (
+            "IsPaused",
+            Value::Bool(status.is_some_and(|status| status.paused)),
+        ),
+        ("Version", Value::str(VERSION)),
+    ]
+}
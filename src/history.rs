@@ -0,0 +1,97 @@
+//! Finished periods, kept across sessions in `history.json`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{clock::Clock, git::Checkout, io::replace};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeriodKind {
+    Work,
+    Break,
+    /// Work cut short by quitting, which doesn't count as a pomodoro
+    Interrupted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub kind: PeriodKind,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// The task selected when the period ended
+    pub task: Option<String>,
+    /// The git checkout worked in, for work
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<Checkout>,
+}
+
+impl Record {
+    pub fn duration(&self) -> chrono::Duration {
+        self.end - self.start
+    }
+
+    fn is_today(&self, clock: &dyn Clock) -> bool {
+        clock.local(self.start).date_naive() == clock.local(clock.now()).date_naive()
+    }
+}
+
+pub struct History {
+    path: PathBuf,
+    pub records: Vec<Record>,
+}
+
+impl History {
+    /// Read the history at `path`, which is empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let records = match fs::read_to_string(path) {
+            Ok(history_file) => serde_json::from_str(&history_file)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            records,
+        })
+    }
+
+    /// Add a record and write the history back to disk.
+    pub fn push(&mut self, record: Record) -> io::Result<()> {
+        self.records.push(record);
+        self.save()
+    }
+
+    /// Like `push`, for a record from the past, which goes where it belongs in time.
+    pub fn insert(&mut self, record: Record) -> io::Result<()> {
+        let index = self
+            .records
+            .partition_point(|existing| existing.start <= record.start);
+        self.records.insert(index, record);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        replace(&self.path, &serde_json::to_string_pretty(&self.records)?)
+    }
+
+    /// The records started on the day `clock` is at.
+    pub fn today<'a>(&'a self, clock: &'a dyn Clock) -> impl Iterator<Item = &'a Record> {
+        (self.records.iter()).filter(move |record| record.is_today(clock))
+    }
+
+    pub fn pomodoros(&self) -> impl Iterator<Item = &Record> {
+        self.records
+            .iter()
+            .filter(|record| record.kind == PeriodKind::Work)
+    }
+
+    pub fn pomodoros_today<'a>(&'a self, clock: &'a dyn Clock) -> impl Iterator<Item = &'a Record> {
+        self.today(clock)
+            .filter(|record| record.kind == PeriodKind::Work)
+    }
+}
@@ -0,0 +1,77 @@
+//! Running user configured shell commands on period transitions.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use crate::timer::Transition;
+
+/// A shell command for each transition, run with `POMORS_*` environment variables describing it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    pub on_work_start: Option<String>,
+    pub on_work_end: Option<String>,
+    pub on_break_start: Option<String>,
+    pub on_break_end: Option<String>,
+}
+
+/// What a hook is told about the transition.
+pub struct Event<'a> {
+    pub transition: Transition,
+    pub task: Option<&'a str>,
+    /// How long the period that's starting will be, or how long the one that ended was
+    pub duration: Duration,
+    /// Which pomodoro of the day this is, or the last one for breaks
+    pub pomodoro: usize,
+}
+
+impl Hooks {
+    fn command(&self, transition: Transition) -> Option<&str> {
+        match transition {
+            Transition::WorkStart => self.on_work_start.as_deref(),
+            Transition::WorkEnd => self.on_work_end.as_deref(),
+            Transition::BreakStart => self.on_break_start.as_deref(),
+            Transition::BreakEnd => self.on_break_end.as_deref(),
+        }
+    }
+}
+
+/// Run the hook for the transition, if there is one, without waiting for it to finish.
+pub fn run(hooks: &Hooks, event: Event) {
+    let Some(command) = hooks.command(event.transition) else {
+        return;
+    };
+
+    let mut command = shell(command);
+    command
+        .env("POMORS_EVENT", event.transition.name())
+        .env("POMORS_TASK", event.task.unwrap_or_default())
+        .env("POMORS_DURATION", event.duration.as_secs().to_string())
+        .env("POMORS_POMODORO", event.pomodoro.to_string())
+        // Anything printed would end up all over the TUI
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    thread::spawn(move || {
+        // The hook's exit status means nothing to pomors, a failing script is its own business
+        let _ = command.status();
+    });
+}
+
+#[cfg(unix)]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
@@ -0,0 +1,408 @@
+//! A small HTTP API to the running timer, for browser dashboards and phone shortcuts. It answers
+//! with JSON. Web pages can only call it from the origins allowed in the config, and a token can
+//! be required of every caller.
+//!
+//! * `GET /state`, `GET /tasks` and `GET /history`
+//! * `POST /pause`, `/resume`, `/skip`, `/stop`, `/start-break` and `/toggle-task`
+//! * `POST /add-time` with `{"secs": 300}` and `POST /tasks` with `{"task": "Write report ~3"}`
+//! * `GET /events` upgrades to a WebSocket that's sent the state, then every event
+//! * `GET /metrics` for Prometheus
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::mpsc::Sender,
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    control::{Command, SharedStatus, SharedTasks},
+    history::History,
+    metrics, websocket,
+};
+
+/// Requests with bigger bodies are turned away.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Who may call the API.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Access {
+    /// Origins of the web pages allowed to call it, like `http://localhost:3000`. Requests from
+    /// pages anywhere else are turned away.
+    pub allowed_origins: Vec<String>,
+    /// Token callers have to send as `Authorization: Bearer <token>`, or as `?token=` where they
+    /// can't set headers, like WebSockets in browsers
+    pub token: Option<String>,
+}
+
+/// What the API answers from and sends commands to.
+#[derive(Clone)]
+pub struct Api {
+    pub access: Access,
+    pub commands: Sender<Command>,
+    pub status: SharedStatus,
+    pub tasks: SharedTasks,
+    pub history_path: PathBuf,
+    pub clients: websocket::Clients,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    /// `token` from the query string
+    token: Option<String>,
+    /// `Origin`, sent by browsers for requests from web pages
+    origin: Option<String>,
+    content_type: Option<String>,
+    authorization: Option<String>,
+    /// `Sec-WebSocket-Key`, sent when asking to upgrade to a WebSocket
+    websocket_key: Option<String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: Value) -> Response {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    fn text(body: String) -> Response {
+        Response {
+            status: 200,
+            content_type: "text/plain; version=0.0.4",
+            body,
+        }
+    }
+
+    fn error(status: u16, error: &str) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": error }).to_string(),
+        }
+    }
+}
+
+/// Listen on `address`, answering requests in the background.
+pub fn serve(address: SocketAddr, api: Api) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let api = api.clone();
+            thread::spawn(move || {
+                // A client that went away needs no answer
+                let _ = handle(stream, &api);
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, api: &Api) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let request = read_request(&stream)?;
+    let response = match &request {
+        Some(request) => match check(request, &api.access) {
+            Err(refused) => refused,
+            Ok(()) if request.method == "GET" && request.path == "/events" => {
+                match &request.websocket_key {
+                    Some(key) => {
+                        return websocket::serve(stream, key, first_event(api), &api.clients)
+                    }
+                    None => Response::error(400, "expected a WebSocket upgrade"),
+                }
+            }
+            Ok(()) => route(request, api),
+        },
+        None => Response::error(400, "bad request"),
+    };
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    };
+    // Only pages from allowed origins get to read the answer
+    let cors = match request.and_then(|request| request.origin) {
+        Some(origin) => format!(
+            "Access-Control-Allow-Origin: {origin}\r\n\
+             Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+             Vary: Origin\r\n"
+        ),
+        None => String::new(),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {reason}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         {cors}\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )
+}
+
+/// Turn away requests from web pages elsewhere, without the token when there is one, and POSTs
+/// that aren't JSON, which pages elsewhere could send as forms without asking first.
+fn check(request: &Request, access: &Access) -> Result<(), Response> {
+    if let Some(origin) = &request.origin {
+        if !access.allowed_origins.contains(origin) {
+            return Err(Response::error(403, "origin not allowed"));
+        }
+    }
+    // Browsers ask before sending JSON from another origin, without the token
+    if request.method == "OPTIONS" {
+        return Ok(());
+    }
+    if let Some(token) = &access.token {
+        let sent = request
+            .authorization
+            .as_deref()
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .or(request.token.as_deref());
+        if sent != Some(token.as_str()) {
+            return Err(Response::error(401, "missing or wrong token"));
+        }
+    }
+    let is_json = request.content_type.as_deref().is_some_and(|content_type| {
+        content_type
+            .split(';')
+            .next()
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+    });
+    if request.method == "POST" && !is_json {
+        return Err(Response::error(
+            415,
+            "expected Content-Type: application/json",
+        ));
+    }
+    Ok(())
+}
+
+/// Read the request line, the headers, which only matter for the length of the body, and the
+/// body. `None` when it isn't HTTP.
+fn read_request(stream: &TcpStream) -> io::Result<Option<Request>> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let method = method.to_string();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = path.to_string();
+    let token = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .map(String::from);
+
+    let mut content_length = 0;
+    let mut origin = None;
+    let mut content_type = None;
+    let mut authorization = None;
+    let mut websocket_key = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("origin") {
+                origin = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("sec-websocket-key") {
+                websocket_key = Some(value.trim().to_string());
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Ok(None);
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Request {
+        method,
+        path,
+        token,
+        origin,
+        content_type,
+        authorization,
+        websocket_key,
+        body,
+    }))
+}
+
+/// The current state, in the shape of the events that follow it.
+fn first_event(api: &Api) -> String {
+    let status = api.status.lock().ok().and_then(|status| status.clone());
+    json!({
+        "event": "state",
+        "state": status.as_ref().map(|status| status.state),
+        "task": status.as_ref().and_then(|status| status.task.clone()),
+        "remaining_secs": status.as_ref().map(|status| status.remaining_secs),
+        "timestamp": chrono::Utc::now(),
+    })
+    .to_string()
+}
+
+#[derive(Deserialize)]
+struct AddTime {
+    secs: u64,
+}
+
+#[derive(Deserialize)]
+struct AddTask {
+    task: String,
+}
+
+fn route(request: &Request, api: &Api) -> Response {
+    let command = match (request.method.as_str(), request.path.as_str()) {
+        // Browsers ask before sending JSON from another origin, which `check` allowed
+        ("OPTIONS", _) => return Response::ok(json!({})),
+        ("GET", "/state") => {
+            let status = api.status.lock().map(|status| status.clone());
+            return Response::ok(json!(status.ok().flatten()));
+        }
+        ("GET", "/tasks") => {
+            let tasks = api.tasks.lock().map(|tasks| tasks.clone());
+            return Response::ok(json!(tasks.unwrap_or_default()));
+        }
+        ("GET", "/metrics") => {
+            let status = api.status.lock().ok().and_then(|status| status.clone());
+            return match History::load(&api.history_path) {
+                Ok(history) => Response::text(metrics::render(status.as_ref(), &history)),
+                Err(e) => Response::error(500, &e.to_string()),
+            };
+        }
+        ("GET", "/history") => {
+            return match History::load(&api.history_path) {
+                Ok(history) => Response::ok(json!(history.records)),
+                Err(e) => Response::error(500, &e.to_string()),
+            }
+        }
+        ("POST", "/pause") => Command::Pause,
+        ("POST", "/resume") => Command::Resume,
+        ("POST", "/skip") => Command::Skip,
+        ("POST", "/stop") => Command::Stop,
+        ("POST", "/start-break") => Command::StartBreak,
+        ("POST", "/toggle-task") => Command::ToggleTask,
+        ("POST", "/add-time") => match serde_json::from_slice::<AddTime>(&request.body) {
+            Ok(AddTime { secs }) => Command::AddTime(Duration::from_secs(secs)),
+            Err(_) => return Response::error(400, r#"expected {"secs": ...}"#),
+        },
+        ("POST", "/tasks") => match serde_json::from_slice::<AddTask>(&request.body) {
+            Ok(AddTask { task }) => Command::AddTask(task),
+            Err(_) => return Response::error(400, r#"expected {"task": ...}"#),
+        },
+        (
+            _,
+            "/state" | "/tasks" | "/history" | "/events" | "/metrics" | "/pause" | "/resume"
+            | "/skip" | "/stop" | "/start-break" | "/toggle-task" | "/add-time",
+        ) => return Response::error(405, "method not allowed"),
+        _ => return Response::error(404, "not found"),
+    };
+    match api.commands.send(command) {
+        Ok(()) => Response::ok(json!({ "ok": true })),
+        Err(_) => Response::error(500, "pomors is quitting"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, origin: Option<&str>) -> Request {
+        Request {
+            method: method.to_string(),
+            path: "/skip".to_string(),
+            token: None,
+            origin: origin.map(String::from),
+            content_type: Some("application/json".to_string()),
+            authorization: None,
+            websocket_key: None,
+            body: Vec::new(),
+        }
+    }
+
+    fn access() -> Access {
+        Access {
+            allowed_origins: vec!["http://localhost:3000".to_string()],
+            token: None,
+        }
+    }
+
+    fn status(result: Result<(), Response>) -> u16 {
+        result.err().map_or(200, |response| response.status)
+    }
+
+    #[test]
+    fn only_allowed_origins_get_in() {
+        let access = access();
+        assert_eq!(status(check(&request("GET", None), &access)), 200);
+        let allowed = request("GET", Some("http://localhost:3000"));
+        assert_eq!(status(check(&allowed, &access)), 200);
+        let elsewhere = request("POST", Some("https://example.com"));
+        assert_eq!(status(check(&elsewhere, &access)), 403);
+        let asking = request("OPTIONS", Some("https://example.com"));
+        assert_eq!(status(check(&asking, &access)), 403);
+    }
+
+    #[test]
+    fn posts_have_to_be_json() {
+        let mut form = request("POST", None);
+        form.content_type = Some("application/x-www-form-urlencoded".to_string());
+        assert_eq!(status(check(&form, &access())), 415);
+        form.content_type = None;
+        assert_eq!(status(check(&form, &access())), 415);
+        form.content_type = Some("application/json; charset=utf-8".to_string());
+        assert_eq!(status(check(&form, &access())), 200);
+    }
+
+    #[test]
+    fn the_token_is_needed_when_set() {
+        let access = Access {
+            token: Some("secret".to_string()),
+            ..access()
+        };
+        let mut request = request("POST", None);
+        assert_eq!(status(check(&request, &access)), 401);
+        request.authorization = Some("Bearer wrong".to_string());
+        assert_eq!(status(check(&request, &access)), 401);
+        request.authorization = Some("Bearer secret".to_string());
+        assert_eq!(status(check(&request, &access)), 200);
+        request.authorization = None;
+        request.token = Some("secret".to_string());
+        assert_eq!(status(check(&request, &access)), 200);
+        // Preflights are sent without it
+        let asking = self::request("OPTIONS", Some("http://localhost:3000"));
+        assert_eq!(status(check(&asking, &access)), 200);
+    }
+}
@@ -0,0 +1,158 @@
+//! Telling i3 or sway about the timer over their IPC socket: running commands when work starts
+//! and stops, e.g. to recolor the focused window, and sending a tick with the state for scripts
+//! subscribed to tick events.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    env, io,
+    path::PathBuf,
+    process::Command,
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use crate::{control::Status, timer::AppState};
+
+const MAGIC: &[u8] = b"i3-ipc";
+const RUN_COMMAND: u32 = 0;
+const SEND_TICK: u32 = 10;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct I3 {
+    /// Run when work starts or is resumed, e.g. `client.focused #e06c75 #e06c75 #ffffff` on sway
+    /// or `mode "focus"`
+    pub on_work: Option<String>,
+    /// Run when work stops: on breaks, pauses and quitting
+    pub on_break: Option<String>,
+}
+
+/// Sends the status to i3 from a thread of its own.
+pub struct Ipc {
+    statuses: Sender<Status>,
+}
+
+impl Ipc {
+    pub fn start(i3: &I3) -> Ipc {
+        let (statuses, received) = mpsc::channel::<Status>();
+        let i3 = i3.clone();
+        thread::spawn(move || {
+            let mut connection = None;
+            let mut sent: Option<(AppState, bool, Option<String>)> = None;
+            for status in received {
+                let current = (status.state, status.paused, status.task.clone());
+                if sent.as_ref() == Some(&current) {
+                    continue;
+                }
+                if connection.is_none() {
+                    connection = ipc::connect().ok();
+                }
+                let Some(socket) = &mut connection else {
+                    continue;
+                };
+                let focusing = |sent: &(AppState, bool, _)| sent.0 == AppState::Working && !sent.1;
+                let command = match (sent.as_ref().map(focusing), focusing(&current)) {
+                    (Some(true), true) | (Some(false), false) => None,
+                    (_, true) => i3.on_work.as_deref(),
+                    (_, false) => i3.on_break.as_deref(),
+                };
+                let tick = format!("pomors {}", serde_json::json!(status));
+                let result = command
+                    .map_or(Ok(()), |command| ipc::send(socket, RUN_COMMAND, command))
+                    .and_then(|()| ipc::send(socket, SEND_TICK, &tick));
+                match result {
+                    Ok(()) => sent = Some(current),
+                    Err(_) => {
+                        connection = None;
+                        sent = None;
+                    }
+                }
+            }
+        });
+        Ipc { statuses }
+    }
+
+    pub fn update(&self, status: Status) {
+        let _ = self.statuses.send(status);
+    }
+}
+
+impl I3 {
+    /// Run `on_break` on quitting, waiting for it so the focus look isn't left behind.
+    pub fn end(&self) {
+        if let (Some(command), Ok(mut socket)) = (&self.on_break, ipc::connect()) {
+            let _ = ipc::send(&mut socket, RUN_COMMAND, command);
+        }
+    }
+}
+
+/// Where the IPC socket is, from the environment or from asking the window manager.
+fn socket_path() -> io::Result<PathBuf> {
+    for variable in ["SWAYSOCK", "I3SOCK"] {
+        if let Some(path) = env::var_os(variable).filter(|path| !path.is_empty()) {
+            return Ok(path.into());
+        }
+    }
+    for wm in ["sway", "i3"] {
+        if let Ok(output) = Command::new(wm).arg("--get-socketpath").output() {
+            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if output.status.success() && !path.is_empty() {
+                return Ok(path.into());
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "neither sway nor i3 is running",
+    ))
+}
+
+#[cfg(unix)]
+mod ipc {
+    use std::{
+        io::{self, Read, Write},
+        os::unix::net::UnixStream,
+        time::Duration,
+    };
+
+    use super::MAGIC;
+
+    pub fn connect() -> io::Result<UnixStream> {
+        let socket = UnixStream::connect(super::socket_path()?)?;
+        socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+        Ok(socket)
+    }
+
+    /// Send a message and wait for the reply, which isn't needed.
+    pub fn send(socket: &mut UnixStream, kind: u32, payload: &str) -> io::Result<()> {
+        let mut message = MAGIC.to_vec();
+        message.extend((payload.len() as u32).to_ne_bytes());
+        message.extend(kind.to_ne_bytes());
+        message.extend(payload.as_bytes());
+        socket.write_all(&message)?;
+
+        let mut header = [0; 14];
+        socket.read_exact(&mut header)?;
+        let length = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]);
+        io::copy(&mut socket.take(length.into()), &mut io::sink())?;
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod ipc {
+    use std::io;
+
+    pub struct UnixStream;
+
+    pub fn connect() -> io::Result<UnixStream> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "i3 and sway need a Unix system",
+        ))
+    }
+
+    pub fn send(_socket: &mut UnixStream, _kind: u32, _payload: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//! Buttons on a Stream Deck or a MIDI controller, sending commands to the timer like the control
+//! socket does.
+//!
+//! Each device is read by a `Backend` in a thread of its own, and opened again if it's unplugged
+//! and plugged back in. Devices are read directly from `/dev`, so only Linux is supported.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+    time::Duration,
+};
+
+use crate::control::Command;
+
+/// How long to wait before opening a device again
+const REOPEN_INTERVAL: Duration = Duration::from_secs(5);
+
+const ELGATO_VENDOR_ID: u32 = 0x0fd9;
+/// Stream Decks whose key reports start with only a report ID, the original and the Minis
+const ONE_BYTE_HEADER_PRODUCTS: [u32; 3] = [0x0060, 0x0063, 0x0090];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Device {
+    Midi,
+    StreamDeck,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Input {
+    pub device: Device,
+    /// The device file, the first one found if not given
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// The command for each button, e.g. `"0": "toggle_pause"` for the first key of a Stream Deck
+    /// or `"note 36": "skip"` for a MIDI pad
+    pub buttons: BTreeMap<String, Command>,
+}
+
+/// Something with buttons to press.
+pub trait Backend: Send {
+    /// Wait for the next button to be pressed, and name it.
+    fn next_press(&mut self) -> io::Result<String>;
+}
+
+impl Input {
+    fn open(&self) -> io::Result<Box<dyn Backend>> {
+        Ok(match self.device {
+            Device::Midi => Box::new(Midi::open(self.path.as_deref())?),
+            Device::StreamDeck => Box::new(StreamDeck::open(self.path.as_deref())?),
+        })
+    }
+}
+
+/// Read the `inputs` in the background, sending the commands their buttons are set to.
+pub fn start(inputs: &[Input], commands: Sender<Command>) {
+    for input in inputs {
+        let input = input.clone();
+        let commands = commands.clone();
+        thread::spawn(move || loop {
+            // Until it's unplugged
+            if let Ok(mut backend) = input.open() {
+                while let Ok(button) = backend.next_press() {
+                    let Some(command) = input.buttons.get(&button) else {
+                        continue;
+                    };
+                    if commands.send(command.clone()).is_err() {
+                        return;
+                    }
+                }
+            }
+            thread::sleep(REOPEN_INTERVAL);
+        });
+    }
+}
+
+/// The first file in `dir` whose name starts with `prefix`.
+fn first_device(dir: &str, prefix: &str) -> io::Result<PathBuf> {
+    let mut devices: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .map(|entry| entry.path())
+        .collect();
+    devices.sort();
+    devices
+        .into_iter()
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no device found"))
+}
+
+/// A raw MIDI port, whose buttons are named `note N` for notes and `cc N` for controllers.
+struct Midi {
+    port: File,
+    /// The status byte of the message being read, which later messages may leave out
+    status: u8,
+    data: Vec<u8>,
+}
+
+impl Midi {
+    fn open(path: Option<&Path>) -> io::Result<Midi> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => first_device("/dev/snd", "midiC")?,
+        };
+        Ok(Midi {
+            port: File::open(path)?,
+            status: 0,
+            data: Vec::new(),
+        })
+    }
+}
+
+impl Backend for Midi {
+    fn next_press(&mut self) -> io::Result<String> {
+        let mut byte = [0];
+        loop {
+            self.port.read_exact(&mut byte)?;
+            match byte[0] {
+                // Clock and other real-time messages can come in the middle of anything
+                0xF8.. => continue,
+                status @ 0x80..=0xF7 => {
+                    self.status = status;
+                    self.data.clear();
+                    continue;
+                }
+                data => self.data.push(data),
+            }
+            let length = match self.status >> 4 {
+                0xC | 0xD => 1,
+                0x8..=0xE => 2,
+                // System messages aren't buttons
+                _ => {
+                    self.data.clear();
+                    continue;
+                }
+            };
+            if self.data.len() < length {
+                continue;
+            }
+            let data = std::mem::take(&mut self.data);
+            match (self.status >> 4, &data[..]) {
+                // A note-on with no velocity is a note-off
+                (0x9, &[note, velocity]) if velocity > 0 => return Ok(format!("note {note}")),
+                (0xB, &[controller, value]) if value > 0 => return Ok(format!("cc {controller}")),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A Stream Deck's HID device, whose keys are named by their number from `0`.
+struct StreamDeck {
+    device: File,
+    /// Where the key states start in a report
+    header: usize,
+    pressed: Vec<bool>,
+}
+
+impl StreamDeck {
+    fn open(path: Option<&Path>) -> io::Result<StreamDeck> {
+        let (path, product) = match path {
+            Some(path) => (path.to_path_buf(), None),
+            None => find_stream_deck()?,
+        };
+        let header = match product {
+            Some(product) if ONE_BYTE_HEADER_PRODUCTS.contains(&product) => 1,
+            _ => 4,
+        };
+        Ok(StreamDeck {
+            device: File::open(path)?,
+            header,
+            pressed: Vec::new(),
+        })
+    }
+}
+
+/// The `hidraw` device of the first Stream Deck plugged in, and its product ID.
+fn find_stream_deck() -> io::Result<(PathBuf, Option<u32>)> {
+    let mut devices: Vec<_> = fs::read_dir("/sys/class/hidraw")?.flatten().collect();
+    devices.sort_by_key(|entry| entry.file_name());
+    for entry in devices {
+        let uevent = fs::read_to_string(entry.path().join("device/uevent")).unwrap_or_default();
+        // e.g. HID_ID=0003:00000FD9:00000080
+        let Some(id) = uevent.lines().find_map(|line| line.strip_prefix("HID_ID=")) else {
+            continue;
+        };
+        let mut parts = id.split(':').skip(1);
+        let mut next_hex = || {
+            parts
+                .next()
+                .and_then(|part| u32::from_str_radix(part, 16).ok())
+        };
+        if next_hex() == Some(ELGATO_VENDOR_ID) {
+            let path = Path::new("/dev").join(entry.file_name());
+            return Ok((path, next_hex()));
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no Stream Deck found",
+    ))
+}
+
+impl Backend for StreamDeck {
+    fn next_press(&mut self) -> io::Result<String> {
+        let mut report = [0; 512];
+        loop {
+            let length = self.device.read(&mut report)?;
+            if length == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            // Only the first report ID has key states
+            if report[0] != 0x01 || length <= self.header {
+                continue;
+            }
+            let keys: Vec<bool> = report[self.header..length]
+                .iter()
+                .map(|&key| key != 0)
+                .collect();
+            let newly_pressed = keys
+                .iter()
+                .enumerate()
+                .position(|(i, &down)| down && !self.pressed.get(i).copied().unwrap_or(false));
+            self.pressed = keys;
+            if let Some(key) = newly_pressed {
+                return Ok(key.to_string());
+            }
+        }
+    }
+}
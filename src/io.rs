@@ -0,0 +1,93 @@
+//! Where pomors keeps its files, and talking to the process it runs in.
+
+use signal_hook::{consts::TERM_SIGNALS, iterator::Signals};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+};
+
+use crate::{
+    audio::AudioBackend,
+    config::{Config, DEFAULT_CONFIG},
+    control,
+};
+
+/// Name of the file of tasks to use when none are given on the command line.
+pub const TASKS_FILE: &str = "tasks.txt";
+/// The tasks last fetched from Todoist
+pub const TODOIST_CACHE_FILE: &str = "todoist.json";
+/// Time entries waiting to be sent to Toggl or Clockify
+pub const TIME_ENTRIES_FILE: &str = "time_entries.json";
+pub const SESSION_FILE: &str = "session.json";
+
+/// Where the config lives, and the files of the default instance.
+pub fn pomors_dir() -> PathBuf {
+    home::home_dir()
+        .expect("Unable to find Home directory.")
+        .join(".config/pomors")
+}
+
+/// Write `contents` to `path` by renaming a file over it, so readers never see half of it and a
+/// crash leaves the old contents.
+pub fn replace(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temporary = PathBuf::from(path);
+    temporary.as_mut_os_string().push(".tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(temporary, path)
+}
+
+/// Read the config in `pomors_dir`, writing out the default one the first time.
+pub fn load_config(pomors_dir: &Path) -> serde_json::Result<Config> {
+    let mut config = DEFAULT_CONFIG;
+    match fs::read_dir(pomors_dir) {
+        Ok(_) => {
+            if let Ok(config_file) = fs::read_to_string(pomors_dir.join("config.json")) {
+                match serde_json::from_str::<Config>(&config_file) {
+                    Ok(parsed) => config = parsed,
+                    Err(e) => eprintln!("Ignoring config.json, it doesn't parse: {e}"),
+                }
+                if !config.alarm_ramp_up.is_zero() && !has_volume_control(config.audio_backend) {
+                    eprintln!(
+                        "alarm_ramp_up is ignored, only the rodio backend can fade the alarm in \
+                        (build with `--features rodio` and set `\"audio_backend\": \"rodio\"`)"
+                    );
+                }
+            }
+        }
+        Err(e) => match e.kind() {
+            io::ErrorKind::NotFound => {
+                fs::create_dir_all(pomors_dir).expect("Failed to created pomors directory.");
+                fs::write(
+                    pomors_dir.join("config.json"),
+                    serde_json::to_string_pretty(&DEFAULT_CONFIG)
+                        .expect("The default config is not serializable."),
+                )
+                .expect("Failed to write config.json.");
+            }
+            _ => panic!("Error reading .config/pomors: {e}"),
+        },
+    };
+    Ok(config)
+}
+
+fn has_volume_control(backend: AudioBackend) -> bool {
+    cfg!(feature = "rodio") && backend == AudioBackend::Rodio
+}
+
+/// Stop the timer when asked to terminate, or when the terminal goes away, so it's cleaned up
+/// after like when quitting.
+pub fn stop_on_signals(commands: Sender<control::Command>) -> io::Result<()> {
+    #[cfg(unix)]
+    let signals = [TERM_SIGNALS, &[signal_hook::consts::SIGHUP]].concat();
+    #[cfg(not(unix))]
+    let signals = TERM_SIGNALS;
+    let mut signals = Signals::new(signals)?;
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            let _ = commands.send(control::Command::Stop);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Taking the JIRA issues assigned to the user as tasks, and logging the pomodoros worked on
+//! them as worklog entries.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{error::Error, thread, time::Duration};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Jira {
+    /// e.g. `https://example.atlassian.net`
+    pub url: String,
+    /// For JIRA Cloud, which takes the email and an API token. Without it the token is sent as
+    /// a personal access token, as JIRA Server and Data Center take it.
+    #[serde(default)]
+    pub email: Option<String>,
+    pub token: String,
+    /// Which issues to take
+    #[serde(default = "default_jql")]
+    pub jql: String,
+}
+
+fn default_jql() -> String {
+    "assignee = currentUser() AND resolution = Unresolved ORDER BY priority DESC".to_string()
+}
+
+pub struct Issue {
+    pub key: String,
+    pub summary: String,
+    /// 0 for Highest down to 4 for Lowest, none for a priority outside JIRA's default ones
+    pub priority: Option<u8>,
+}
+
+fn priority(name: &str) -> Option<u8> {
+    ["Highest", "High", "Medium", "Low", "Lowest"]
+        .iter()
+        .position(|&priority| priority == name)
+        .map(|i| i as u8)
+}
+
+impl Jira {
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let authorization = match &self.email {
+            Some(email) => format!(
+                "Basic {}",
+                STANDARD.encode(format!("{email}:{}", self.token))
+            ),
+            None => format!("Bearer {}", self.token),
+        };
+        let url = format!("{}/rest/api/2{path}", self.url.trim_end_matches('/'));
+        ureq::request(method, &url)
+            .set("Authorization", &authorization)
+            .timeout(TIMEOUT)
+    }
+
+    pub fn issues(&self) -> Result<Vec<Issue>, Box<dyn Error>> {
+        let found: Value = self
+            .request("GET", "/search")
+            .query("jql", &self.jql)
+            .query("fields", "summary,priority")
+            .query("maxResults", "100")
+            .call()?
+            .into_json()?;
+        Ok(found["issues"]
+            .as_array()
+            .ok_or("JIRA didn't send a list of issues")?
+            .iter()
+            .filter_map(|issue| {
+                Some(Issue {
+                    key: issue["key"].as_str()?.to_string(),
+                    summary: issue["fields"]["summary"].as_str()?.to_string(),
+                    priority: issue["fields"]["priority"]["name"]
+                        .as_str()
+                        .and_then(priority),
+                })
+            })
+            .collect())
+    }
+
+    /// Add a worklog entry to the issue, without waiting for the response.
+    pub fn log_work(&self, key: &str, start: DateTime<Utc>, end: DateTime<Utc>) {
+        // JIRA rejects entries under a minute
+        let seconds = (end - start).num_seconds().max(60);
+        let request = self.request("POST", &format!("/issue/{key}/worklog"));
+        let body = json!({
+            "started": start.format("%Y-%m-%dT%H:%M:%S%.3f%z").to_string(),
+            "timeSpentSeconds": seconds,
+            "comment": "Pomodoro tracked in pomors",
+        });
+        thread::spawn(move || {
+            // A rejected worklog isn't retried, the pomodoro is still in the history to log by hand
+            let _ = request.send_json(body);
+        });
+    }
+}
@@ -0,0 +1,223 @@
+//! Which key does what. Key handling, the help overlay and the key hints are all driven by the
+//! keymaps here.
+
+use crossterm::event::KeyCode;
+
+use crate::ui::Tab;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    NextTask,
+    PreviousTask,
+    ToggleTask,
+    BackspaceTask,
+    /// Start typing in a new task, or add the one typed in
+    AddTask,
+    ToggleHelp,
+    NextTab,
+    ShowTab(Tab),
+    ToggleMute,
+    ToggleTimerOnly,
+    ToggleTasksOnly,
+    CycleSort,
+    TogglePause,
+    PageDown,
+    PageUp,
+    FirstTask,
+    LastTask,
+    /// Answer yes to a question
+    Confirm,
+    /// Answer no to a question
+    Cancel,
+}
+
+/// What's on screen, which decides what the keys do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The timer or the task list
+    Tasks,
+    /// A tab without the task list, like stats
+    View,
+    Help,
+    /// A yes/no question
+    Confirm,
+    /// Typing in a new task
+    Input,
+}
+
+impl Action {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::NextTask => "Select the next task",
+            Action::PreviousTask => "Select the previous task",
+            Action::ToggleTask => "Mark the task as done or not done",
+            Action::BackspaceTask => "Delete the last character of the task name",
+            Action::AddTask => "Add a task",
+            Action::ToggleHelp => "Show or hide this help",
+            Action::NextTab => "Go to the next tab",
+            Action::ShowTab(Tab::Timer) => "Show the timer",
+            Action::ShowTab(Tab::Tasks) => "Show the task list",
+            Action::ShowTab(Tab::Stats) => "Show the statistics",
+            Action::ShowTab(Tab::History) => "Show the history",
+            Action::ToggleMute => "Mute or unmute sounds",
+            Action::ToggleTimerOnly => "Hide or show the task list under the timer",
+            Action::ToggleTasksOnly => "Hide or show the timer above the task list",
+            Action::CycleSort => "Change the order the task list is shown in",
+            Action::TogglePause => "Pause or resume the timer",
+            Action::PageDown => "Select a page further down the task list",
+            Action::PageUp => "Select a page further up the task list",
+            Action::FirstTask => "Select the first task",
+            Action::LastTask => "Select the last task",
+            Action::Confirm => "Yes",
+            Action::Cancel => "No",
+        }
+    }
+
+    /// A word or two for the key hints, or `None` to leave it out of them.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Action::Quit => Some("quit"),
+            Action::NextTask => Some("next"),
+            Action::PreviousTask => Some("previous"),
+            Action::ToggleTask => Some("done"),
+            Action::AddTask => Some("add"),
+            Action::ToggleHelp => Some("help"),
+            Action::NextTab => Some("tabs"),
+            Action::ToggleMute => Some("mute"),
+            Action::CycleSort => Some("sort"),
+            Action::TogglePause => Some("pause"),
+            Action::Confirm => Some("yes"),
+            Action::Cancel => Some("no"),
+            _ => None,
+        }
+    }
+
+    /// Whether the action does anything in `mode`, outside of dialogs.
+    fn is_available(&self, mode: Mode) -> bool {
+        match mode {
+            Mode::Tasks => true,
+            Mode::View => matches!(
+                self,
+                Action::Quit
+                    | Action::ToggleHelp
+                    | Action::NextTab
+                    | Action::ShowTab(_)
+                    | Action::ToggleMute
+                    | Action::TogglePause
+            ),
+            Mode::Help | Mode::Confirm | Mode::Input => false,
+        }
+    }
+}
+
+pub const KEYMAP: &[(KeyCode, Action)] = &[
+    (KeyCode::Esc, Action::Quit),
+    (KeyCode::Char('q'), Action::Quit),
+    (KeyCode::Down, Action::NextTask),
+    (KeyCode::Up, Action::PreviousTask),
+    (KeyCode::Enter, Action::ToggleTask),
+    (KeyCode::Backspace, Action::BackspaceTask),
+    (KeyCode::Char('a'), Action::AddTask),
+    (KeyCode::Char('?'), Action::ToggleHelp),
+    (KeyCode::Tab, Action::NextTab),
+    (KeyCode::Char('1'), Action::ShowTab(Tab::Timer)),
+    (KeyCode::Char('2'), Action::ShowTab(Tab::Tasks)),
+    (KeyCode::Char('3'), Action::ShowTab(Tab::Stats)),
+    (KeyCode::Char('4'), Action::ShowTab(Tab::History)),
+    (KeyCode::Char('m'), Action::ToggleMute),
+    (KeyCode::Char('f'), Action::ToggleTimerOnly),
+    (KeyCode::Char('t'), Action::ToggleTasksOnly),
+    (KeyCode::Char('s'), Action::CycleSort),
+    (KeyCode::Char(' '), Action::TogglePause),
+    (KeyCode::PageDown, Action::PageDown),
+    (KeyCode::PageUp, Action::PageUp),
+    (KeyCode::Home, Action::FirstTask),
+    (KeyCode::End, Action::LastTask),
+];
+
+/// Keys while the help is open, which take precedence over `KEYMAP`.
+pub const HELP_KEYMAP: &[(KeyCode, Action)] = &[(KeyCode::Esc, Action::ToggleHelp)];
+
+/// Keys for yes/no questions. Nothing else works until one of them is pressed.
+pub const CONFIRM_KEYMAP: &[(KeyCode, Action)] = &[
+    (KeyCode::Char('y'), Action::Confirm),
+    (KeyCode::Char('n'), Action::Cancel),
+    (KeyCode::Esc, Action::Cancel),
+];
+
+/// Keys while typing in a new task. Other characters and Backspace edit it.
+pub const INPUT_KEYMAP: &[(KeyCode, Action)] = &[
+    (KeyCode::Enter, Action::AddTask),
+    (KeyCode::Esc, Action::Cancel),
+];
+
+/// The keys that do something in `mode`, in the order they're looked up.
+pub fn bindings(mode: Mode) -> impl Iterator<Item = &'static (KeyCode, Action)> {
+    let dialog = match mode {
+        Mode::Help => HELP_KEYMAP,
+        Mode::Confirm => CONFIRM_KEYMAP,
+        Mode::Input => INPUT_KEYMAP,
+        Mode::Tasks | Mode::View => &[],
+    };
+    let base_mode = if mode == Mode::Help {
+        Mode::Tasks
+    } else {
+        mode
+    };
+    dialog.iter().chain(
+        KEYMAP
+            .iter()
+            .filter(move |(_, action)| action.is_available(base_mode)),
+    )
+}
+
+pub fn action(mode: Mode, key: KeyCode) -> Option<Action> {
+    bindings(mode)
+        .find(|(bound_key, _)| *bound_key == key)
+        .map(|(_, action)| *action)
+}
+
+/// `key hint` pairs for what can be done in `mode`, one per action.
+pub fn hints(mode: Mode) -> Vec<(String, &'static str)> {
+    let mut hints: Vec<(String, &'static str)> = Vec::new();
+    let bindings: Vec<_> = match mode {
+        // Only closing it is worth a hint while the help lists everything
+        Mode::Help => HELP_KEYMAP.iter().collect(),
+        _ => bindings(mode).collect(),
+    };
+    for (key, action) in bindings {
+        if let Some(hint) = action.hint() {
+            let hint = match mode {
+                Mode::Help => "close",
+                Mode::Input if *action == Action::Cancel => "cancel",
+                _ => hint,
+            };
+            if !hints.iter().any(|(_, shown)| *shown == hint) {
+                hints.push((key_name(*key), hint));
+            }
+        }
+    }
+    hints
+}
+
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        other => format!("{other:?}"),
+    }
+}
@@ -0,0 +1,54 @@
+//! A pomodoro timer for the terminal. The binary parses the command line and sets up the terminal;
+//! everything else is here, for other frontends to use.
+
+pub mod announce;
+pub mod app;
+pub mod attach;
+pub mod audio;
+pub mod background;
+pub mod bigtext;
+pub mod calendar;
+pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod control;
+#[cfg(unix)]
+pub mod dbus;
+pub mod discord;
+pub mod distractions;
+pub mod dnd;
+pub mod event;
+pub mod format;
+pub mod gauge;
+pub mod git;
+pub mod github;
+#[cfg(unix)]
+pub mod gnome_pomodoro;
+pub mod history;
+pub mod hooks;
+pub mod http;
+pub mod i3;
+pub mod input;
+pub mod io;
+pub mod jira;
+pub mod keymap;
+pub mod lock;
+pub mod metrics;
+#[cfg(unix)]
+pub mod mpris;
+pub mod mqtt;
+pub mod notify;
+pub mod obsidian;
+pub mod push;
+pub mod session;
+pub mod slack;
+pub mod status_file;
+pub mod task;
+pub mod theme;
+pub mod time_tracking;
+pub mod timer;
+pub mod todoist;
+pub mod todotxt;
+pub mod ui;
+pub mod webhook;
+pub mod websocket;
@@ -0,0 +1,58 @@
+//! Making sure only one timer runs per instance, as two would write over each other's history and
+//! session.
+
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::control;
+
+const PID_FILE: &str = "pomors.pid";
+
+/// Held while the timer runs. Dropping it removes the PID file and the control socket.
+pub struct Lock {
+    dir: PathBuf,
+}
+
+impl Lock {
+    /// Take the lock on the instance in `dir`, writing our PID to its PID file. Fails if another
+    /// timer holds it, and takes over from one that didn't get to clean up.
+    pub fn acquire(dir: &Path) -> Result<Lock, Box<dyn Error>> {
+        let pid_path = dir.join(PID_FILE);
+        let mut file = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&pid_path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if control::query_status(&control::socket_path(dir))?.is_some() {
+                    let pid = fs::read_to_string(&pid_path).unwrap_or_default();
+                    return Err(format!(
+                        "pomors is already running with PID {}, `pomors attach` shows it and \
+                         `--instance <name>` runs another one",
+                        pid.trim()
+                    )
+                    .into());
+                }
+                fs::File::create(&pid_path)?
+            }
+            Err(e) => return Err(e.into()),
+        };
+        writeln!(file, "{}", process::id())?;
+        Ok(Lock {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(control::socket_path(&self.dir));
+        let _ = fs::remove_file(self.dir.join(PID_FILE));
+    }
+}
@@ -1,489 +1,258 @@
-use chrono::{DateTime, Utc};
+use chrono::{Local, Utc};
 use clap::Parser;
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+use pomors::{
+    app::{build_app, start_session, App},
+    attach,
+    cli::{self, Cli, Command, DaemonArgs, ExportFormat, Period, StartArgs, StatusFormat},
+    config::Config,
+    control, git,
+    history::{self, History, PeriodKind},
+    io::{load_config, pomors_dir, SESSION_FILE, TASKS_FILE},
+    notify, session,
+    theme::Theme,
+    timer::next_time_of_day,
+    ui::{self, COMPACT_GAUGE_WIDTH},
 };
-use rusty_audio::Audio;
-use serde::{Deserialize, Serialize};
 use std::{
+    env,
     error::Error,
-    fs, io, thread,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::Path,
+    process, thread,
     time::{Duration, Instant},
 };
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph},
-    Frame, Terminal,
-};
-
-#[derive(Serialize, Deserialize)]
-struct Task {
-    name: String,
-    is_complete: bool,
-    work_periods: Vec<(DateTime<Utc>, DateTime<Utc>)>,
-}
 
-impl Task {
-    fn new(name: &str) -> Self {
-        Self {
-            name: name.to_string(),
-            is_complete: false,
-            work_periods: Vec::new(),
-        }
-    }
+fn main() -> Result<(), Box<dyn Error>> {
+    // Get args
+    let cli = Cli::parse();
 
-    fn activate(&mut self) {
-        let time = Utc::now();
-        self.work_periods.push((time.clone(), time))
-    }
+    // Get config
+    let pomors_dir = pomors_dir();
 
-    fn deactivate(&mut self) {
-        if let Some(work_period) = self.work_periods.last_mut() {
-            if work_period.0 != work_period.1 {
-                return;
-            }
+    let mut config = load_config(&pomors_dir)?;
 
-            work_period.1 = Utc::now()
+    // Each instance has its own history, tasks and socket, sharing the config
+    let dir = match &cli.instance {
+        Some(instance) => {
+            let dir = pomors_dir.join("instances").join(instance);
+            fs::create_dir_all(&dir)?;
+            dir
         }
-    }
-
-    fn task_total_duration(&self) -> chrono::Duration {
-        self.work_periods
-            .iter()
-            .fold(chrono::Duration::zero(), |acc, work_period| {
-                acc + (work_period.1 - work_period.0)
-            })
-    }
-}
-
-struct StatefulList {
-    state: ListState,
-    items: Vec<Task>,
-}
-
-impl StatefulList {
-    fn with_items(items: Vec<Task>) -> StatefulList {
-        StatefulList {
-            state: ListState::default(),
-            items,
+        None => pomors_dir.clone(),
+    };
+    let history_path = dir.join("history.json");
+    match cli.command {
+        Some(Command::Start(args)) => start(args, config, &dir),
+        None => start(cli.start, config, &dir),
+        Some(Command::Daemon(args)) => daemon(args, config, &dir),
+        Some(Command::Once {
+            duration,
+            task,
+            alerts,
+        }) => {
+            alerts.apply(&mut config);
+            once(duration, task, &config, &dir)
         }
-    }
-
-    fn next(&mut self) {
-        if let Some(selected_task) = self.get_selected_mut() {
-            selected_task.deactivate()
+        Some(Command::Status { format }) => Ok(cli::status(
+            control::query_status(&control::socket_path(&dir))?,
+            if cli.json { StatusFormat::Json } else { format },
+        )?),
+        Some(Command::Tmux) => Ok(cli::status(
+            control::query_status(&control::socket_path(&dir))?,
+            if cli.json {
+                StatusFormat::Json
+            } else {
+                StatusFormat::Tmux
+            },
+        )?),
+        Some(Command::Attach) => attach(&config, &dir),
+        Some(Command::Plan(args)) => {
+            args.lengths.apply(&mut config);
+            cli::plan(
+                &config,
+                Local::now(),
+                args.until.map(next_time_of_day),
+                args.cycles,
+            );
+            Ok(())
         }
-
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.items.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
-        self.state.select(Some(i));
-        if let Some(selected_task) = self.get_selected_mut() {
-            selected_task.activate()
+        Some(Command::Completions { shell }) => {
+            cli::completions(shell);
+            Ok(())
         }
-    }
-
-    fn previous(&mut self) {
-        if let Some(selected_task) = self.get_selected_mut() {
-            selected_task.deactivate()
+        Some(Command::Stats) => Ok(cli::stats(&History::load(&history_path)?, cli.json)?),
+        Some(Command::Config) => Ok(cli::config(
+            &pomors_dir.join("config.json"),
+            &config,
+            cli.json,
+        )?),
+        Some(Command::Export { format }) => Ok(cli::export(
+            &History::load(&history_path)?,
+            if cli.json { ExportFormat::Json } else { format },
+        )?),
+        Some(Command::Pause) => send(control::Command::Pause, &dir),
+        Some(Command::Resume) => send(control::Command::Resume, &dir),
+        Some(Command::Skip) => send(control::Command::Skip, &dir),
+        Some(Command::Stop) => send(control::Command::Stop, &dir),
+        Some(Command::ToggleTask) => send(control::Command::ToggleTask, &dir),
+        Some(Command::Log { task, duration, at }) => {
+            let record = cli::log(task, duration, at)?;
+            add_to_history(record, &dir)
         }
-
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.items.len() - 1
-                } else {
-                    i - 1
-                }
+        Some(Command::Add { tasks }) => {
+            cli::add(&dir.join(TASKS_FILE), &tasks)?;
+            // The running instance picks them up straight away
+            for task in tasks {
+                control::send(
+                    &control::socket_path(&dir),
+                    &control::Command::AddTask(task),
+                )?;
             }
-            None => 0,
-        };
-        self.state.select(Some(i));
-
-        if let Some(selected_task) = self.get_selected_mut() {
-            selected_task.activate()
-        }
-    }
-
-    fn unselect(&mut self) {
-        if let Some(selected_task) = self.get_selected_mut() {
-            selected_task.deactivate()
+            Ok(())
         }
-        self.state.select(None);
     }
-
-    fn get_selected_mut(&mut self) -> Option<&mut Task> {
-        if let Some(selected) = self.state.selected() {
-            Some(&mut self.items[selected])
-        } else {
-            None
-        }
-    }
-
-    fn get_selected(&self) -> Option<&Task> {
-        if let Some(selected) = self.state.selected() {
-            Some(&self.items[selected])
-        } else {
-            None
-        }
-    }
-}
-
-struct Period {
-    start: Instant,
-    length: Duration,
 }
 
-enum AppState {
-    Working,
-    TakingABreak,
-}
-struct App {
-    pomodoro_length: Duration,
-    break_length: Duration,
-    tasks: StatefulList,
-    state: AppState,
-    start_of_period: Instant,
-}
-
-impl App {
-    fn new(task_list: Vec<String>, pomodoro_length: Duration, break_length: Duration) -> App {
-        App {
-            state: AppState::Working,
-            pomodoro_length,
-            break_length,
-            start_of_period: Instant::now(),
-            tasks: StatefulList::with_items(
-                task_list
-                    .iter()
-                    .map(|name| Task::new(name.trim()))
-                    .collect(),
-            ),
-        }
-    }
-
-    fn period_length(&self) -> Duration {
-        match self.state {
-            AppState::Working => self.pomodoro_length,
-            AppState::TakingABreak => self.break_length,
-        }
+/// Send `command` to the running instance, failing if there isn't one.
+fn send(command: control::Command, dir: &Path) -> Result<(), Box<dyn Error>> {
+    if control::send(&control::socket_path(dir), &command)? {
+        Ok(())
+    } else {
+        Err("pomors isn't running".into())
     }
+}
 
-    fn on_tick(&mut self) {
-        if self.elapsed() > self.period_length() {
-            match self.state {
-                AppState::Working => self.state = AppState::TakingABreak,
-                AppState::TakingABreak => self.state = AppState::Working,
-            }
-
-            let mut audio = Audio::new();
-            audio.add("startup", "creepy-church-bell-33827.mp3"); // Load the sound, give it a name
-            audio.play("startup"); // Execution continues while playback occurs in another thread.
-            thread::sleep(Duration::from_secs(5));
-
-            self.start_of_period = Instant::now();
-        }
-    }
+/// Exit code when a pomodoro was quit in the middle of. Errors exit with 1.
+const EXIT_INTERRUPTED: i32 = 2;
 
-    fn elapsed(&self) -> Duration {
-        Instant::now() - self.start_of_period
+/// Show the timer running in the instance in `dir`.
+fn attach(config: &Config, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut theme = config.theme.theme();
+    if env::var_os("NO_COLOR").is_some_and(|no_color| !no_color.is_empty()) {
+        theme = Theme::NO_COLOR;
     }
+    attach::run(&control::socket_path(dir), theme, config.gauge_glyphs)
+}
 
-    fn remaining(&self) -> Duration {
-        self.period_length().saturating_sub(self.elapsed())
+/// Run the timer in the terminal, or show the one that's already running.
+fn start(mut args: StartArgs, config: Config, dir: &Path) -> Result<(), Box<dyn Error>> {
+    if control::query_status(&control::socket_path(dir))?.is_some() {
+        return attach(&config, dir);
     }
 
-    fn set_current(&mut self) {
-        if let Some(selected_task) = self.tasks.get_selected_mut() {
-            selected_task.is_complete = true;
+    let timer = &mut args.timer;
+    let new_session = timer.has_task_source() || timer.start_with == Period::Break;
+    if !timer.resume && !new_session && io::stdin().is_terminal() {
+        if let Some(session) = session::Session::load(&dir.join(SESSION_FILE))? {
+            timer.resume = session.ask_to_resume()?;
         }
     }
-
-    fn reset_current(&mut self) {
-        if let Some(selected_task) = self.tasks.get_selected_mut() {
-            selected_task.is_complete = false;
-        }
+    let mut app = build_app(args.timer, config, dir)?;
+    app.compact = args.compact;
+    if let Some(theme) = args.theme {
+        app.theme = theme.theme();
     }
-
-    fn toggle_current_task(&mut self) {
-        if let Some(selected_task) = self.tasks.get_selected_mut() {
-            selected_task.is_complete = !selected_task.is_complete;
-        }
+    if args.no_color || env::var_os("NO_COLOR").is_some_and(|no_color| !no_color.is_empty()) {
+        app.theme = Theme::NO_COLOR;
     }
-
-    fn get_current_task_name(&self) -> Option<&String> {
-        if let Some(selected_task) = self.tasks.get_selected() {
-            Some(&selected_task.name)
-        } else {
-            None
-        }
-    }
-
-    fn backspace_task(&mut self) {
-        if let Some(task) = self.tasks.get_selected_mut() {
-            if task.name.len() != 0 {
-                task.name.truncate(task.name.len() - 1)
-            }
-        }
+    let lock = start_session(&mut app, dir)?;
+
+    let res = run_in_terminal(&mut app);
+    app.end_do_not_disturb();
+    drop(lock);
+    println!("{}", app.close_session());
+    res?;
+    if app.interrupted {
+        process::exit(EXIT_INTERRUPTED);
     }
+    Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Config {
-    pomodoro_length: Duration,
-    break_length: Duration,
+/// Take over the terminal to show `app`, handing it back when the app is quit or fails.
+fn run_in_terminal(app: &mut App) -> io::Result<()> {
+    let mut guard = ui::TerminalGuard::enter()?;
+    ui::run(&mut guard.terminal, app)
 }
 
-const DEFAULT_CONFIG: Config = Config {
-    pomodoro_length: Duration::from_secs(25 * 60),
-    break_length: Duration::from_secs(5 * 60),
-};
-
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// List of tasks
-    #[clap(short, long, value_parser, num_args = 1.., value_delimiter = ',')]
-    task_list: Vec<String>,
-
-    /// Length of one pomodoro [min]
-    #[arg(short, long, default_value_t = 25)]
-    length: u64,
-}
-
-fn main() -> Result<(), Box<dyn Error>> {
-    // Get args
-    let args = Args::parse();
-
-    let home_dir = home::home_dir().expect("Unable to find Home directory.");
-
-    // Get config
-    let pomors_dir = home_dir.join(".config/pomors");
+/// Count down `duration` on a single line of the terminal, then alert and record it.
+fn once(
+    duration: Duration,
+    task: Option<String>,
+    config: &Config,
+    dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let started_at = Utc::now();
+    let start = Instant::now();
+    let task_name = task.as_deref().unwrap_or_default();
 
-    match fs::read_dir(&pomors_dir) {
-        Ok(_) => {
-            if let Ok(config_file) = fs::read_to_string(pomors_dir.join("config.json")) {
-                let _config = serde_json::from_str::<Config>(&config_file);
-            }
+    let mut stdout = io::stdout();
+    loop {
+        let elapsed = start.elapsed().min(duration);
+        let remaining = (duration - elapsed).as_secs_f64().ceil() as u64;
+        let bar = config.gauge_glyphs.bar(
+            elapsed.as_secs_f64() / duration.as_secs_f64(),
+            COMPACT_GAUGE_WIDTH,
+        );
+        write!(
+            stdout,
+            "\r[{bar}] {:02}:{:02} {task_name}",
+            remaining / 60,
+            remaining % 60
+        )?;
+        stdout.flush()?;
+        if elapsed >= duration {
+            break;
         }
-        Err(e) => match e.kind() {
-            io::ErrorKind::NotFound => {
-                fs::create_dir_all(&pomors_dir).expect("Failed to created pomors directory.");
-                fs::write(
-                    pomors_dir.join("config.json"),
-                    serde_json::to_string_pretty(&DEFAULT_CONFIG)
-                        .expect("The default config is not serializable."),
-                )
-                .expect("Failed to write config.json.");
-            }
-            _ => panic!("Error reading .config/pomors: {e}"),
+        thread::sleep(Duration::from_secs(1).min(duration - elapsed));
+    }
+    writeln!(stdout)?;
+
+    add_to_history(
+        history::Record {
+            kind: PeriodKind::Work,
+            start: started_at,
+            end: Utc::now(),
+            task: task.clone(),
+            git: git::current(),
         },
-    };
-
-    // setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    // create app and run it
-    let tick_rate = Duration::from_millis(250);
-    let mut app = App::new(
-        args.task_list,
-        Duration::from_secs(args.length * 60),
-        Duration::from_secs(5 * 60),
-    );
-
-    // Select the first task
-    app.tasks.next();
-    let res = run_app(&mut terminal, app, tick_rate);
-
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
+        dir,
     )?;
-    terminal.show_cursor()?;
-
-    if let Err(err) = res {
-        println!("{:?}", err)
+    if config.sound {
+        notify::bell();
+    }
+    if config.notifications {
+        notify::desktop("Time's up", task_name);
     }
-
     Ok(())
 }
 
-fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
-    mut app: App,
-    tick_rate: Duration,
-) -> io::Result<()> {
-    let mut last_tick = Instant::now();
-    loop {
-        terminal.draw(|f| planner_ui(f, &mut app))?;
-
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Esc => return Ok(()),
-                    KeyCode::Down => app.tasks.next(),
-                    KeyCode::Up => app.tasks.previous(),
-                    KeyCode::Enter => app.toggle_current_task(),
-                    KeyCode::Backspace => app.backspace_task(),
-                    _ => {}
-                }
-            }
-        }
-        if last_tick.elapsed() >= tick_rate {
-            app.on_tick();
-            last_tick = Instant::now();
-        }
+/// Add `record` to the history through the running instance if there is one, as it would write
+/// over it with its own copy of the history.
+fn add_to_history(record: history::Record, dir: &Path) -> Result<(), Box<dyn Error>> {
+    if !control::send(
+        &control::socket_path(dir),
+        &control::Command::Log(record.clone()),
+    )? {
+        History::load(&dir.join("history.json"))?.insert(record)?;
     }
+    Ok(())
 }
 
-fn pomodoro_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints(
-            [
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-            ]
-            .as_ref(),
-        )
-        .split(f.size());
-
-    let remaining_min = app.remaining().as_secs() / 60;
-    let remaining_secs = app.remaining().as_secs() % 60;
-
-    let (action, color) = match app.state {
-        AppState::Working => ("Task", Color::Red),
-        AppState::TakingABreak => ("Break", Color::Green),
-    };
-
-    let gauge = Gauge::default()
-        .block(
-            Block::default()
-                .title(Span::styled(" Pomodoro ", Style::default().fg(color)))
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(color)),
-        )
-        .gauge_style(Style::default().fg(color))
-        .percent(
-            (app.elapsed().as_millis() * 100 / app.period_length().as_millis()).min(100) as u16,
-        );
-    f.render_widget(gauge, chunks[0]);
-
-    let time_remaining_text = if !app.remaining().is_zero() {
-        format!("{remaining_min} min {remaining_secs} secs")
-    } else {
-        format!("{action} completed")
-    };
-
-    let time = Spans::from(Span::styled(
-        time_remaining_text,
-        Style::default().fg(color),
-    ));
-
-    let q_to_quit = Spans::from(Span::styled("Press ESC to quit", Style::default().fg(color)));
-
-    let paragraph = Paragraph::new(vec![time, q_to_quit])
-        .style(Style::default())
-        .block(Block::default());
-
-    f.render_widget(paragraph, chunks[1]);
-
-    let items: Vec<ListItem> = app
-        .tasks
-        .items
-        .iter()
-        .map(|task| {
-            let color = if task.is_complete {
-                Color::Green
-            } else {
-                Color::Red
-            };
-            ListItem::new(format!(
-                "{} : {:?}: {}",
-                task.name,
-                task.task_total_duration(),
-                task.work_periods.len()
-            ))
-            .style(Style::default().fg(color))
-        })
-        .collect();
-
-    let items = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(" Task List ")
-                .border_style(Style::default().fg(color)),
-        )
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-        .highlight_symbol(">> ");
-
-    // We can now render the item list
-    f.render_stateful_widget(items, chunks[2], &mut app.tasks.state);
-}
-
-fn planner_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
-    let color = Color::LightBlue;
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([Constraint::Ratio(1, 3)].as_ref())
-        .split(f.size());
+/// Run the timer without a UI, for as long as the process lives.
+fn daemon(args: DaemonArgs, config: Config, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut app = build_app(args.timer, config, dir)?;
+    app.events = args.events;
+    let lock = start_session(&mut app, dir)?;
 
-    let items: Vec<ListItem> = app
-        .tasks
-        .items
-        .iter()
-        .map(|task| {
-            let color = if task.is_complete {
-                Color::Green
-            } else {
-                Color::Red
-            };
-            ListItem::new(format!(
-                "{} : {:?}: {}",
-                task.name,
-                task.task_total_duration(),
-                task.work_periods.len()
-            ))
-            .style(Style::default().fg(color))
-        })
-        .collect();
+    app.run();
 
-    let items = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(" Task List ")
-                .border_style(Style::default().fg(color)),
-        )
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-        .highlight_symbol(">> ");
-
-    // We can now render the item list
-    f.render_stateful_widget(items, chunks[0], &mut app.tasks.state);
+    app.end_do_not_disturb();
+    drop(lock);
+    // Stdout is for the events
+    eprintln!("{}", app.close_session());
+    if app.interrupted {
+        process::exit(EXIT_INTERRUPTED);
+    }
+    Ok(())
 }
@@ -0,0 +1,95 @@
+//! The timer and history in the [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/),
+//! for graphing focus over time.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    control::Status,
+    history::{History, PeriodKind},
+    timer::AppState,
+};
+
+/// The metrics for the current `status` and everything in `history`.
+pub fn render(status: Option<&Status>, history: &History) -> String {
+    let mut pomodoros = 0;
+    let mut focus: BTreeMap<&str, i64> = BTreeMap::new();
+    for record in &history.records {
+        if record.kind == PeriodKind::Work {
+            pomodoros += 1;
+        }
+        if record.kind != PeriodKind::Break {
+            *focus
+                .entry(record.task.as_deref().unwrap_or(""))
+                .or_default() += record.duration().num_seconds();
+        }
+    }
+
+    let mut out = String::new();
+    metric(
+        &mut out,
+        "pomors_pomodoros_completed_total",
+        "counter",
+        "Pomodoros worked to the end",
+    );
+    let _ = writeln!(out, "pomors_pomodoros_completed_total {pomodoros}");
+
+    metric(
+        &mut out,
+        "pomors_focus_seconds_total",
+        "counter",
+        "Time worked, by the task selected at the end of each period",
+    );
+    for (task, secs) in focus {
+        let _ = writeln!(
+            out,
+            "pomors_focus_seconds_total{{task=\"{}\"}} {secs}",
+            escape(task)
+        );
+    }
+
+    metric(
+        &mut out,
+        "pomors_current_state",
+        "gauge",
+        "1 for the state the timer is in",
+    );
+    let current = match status.map(|status| status.state) {
+        Some(AppState::Working) => "working",
+        Some(AppState::TakingABreak) => "break",
+        None => "stopped",
+    };
+    for state in ["working", "break", "stopped"] {
+        let value = u8::from(state == current);
+        let _ = writeln!(out, "pomors_current_state{{state=\"{state}\"}} {value}");
+    }
+
+    metric(
+        &mut out,
+        "pomors_paused",
+        "gauge",
+        "1 while the timer is paused",
+    );
+    let paused = status.is_some_and(|status| status.paused);
+    let _ = writeln!(out, "pomors_paused {}", u8::from(paused));
+
+    metric(
+        &mut out,
+        "pomors_seconds_remaining",
+        "gauge",
+        "Time left in the current period",
+    );
+    let remaining = status.map_or(0, |status| status.remaining_secs);
+    let _ = writeln!(out, "pomors_seconds_remaining {remaining}");
+    out
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
@@ -0,0 +1,219 @@
+//! Offering the timer as a media player over
+//! [MPRIS](https://specifications.freedesktop.org/mpris-spec/latest/), so desktop media widgets
+//! show it and media keys pause and resume it.
+//!
+//! Play and pause are resume and pause, next is skip, and the "track" is the current period: its
+//! title is the task, and its length and position are the period's.
+
+use std::{io, process, sync::mpsc::Sender, thread, time::Duration};
+
+use crate::{
+    control::{Command, SharedStatus, Status},
+    dbus::{Connection, Message, Value, INTROSPECTABLE, PEER, PROPERTIES},
+    timer::AppState,
+};
+
+const NAME: &str = "org.mpris.MediaPlayer2.pomors";
+const PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT: &str = "org.mpris.MediaPlayer2";
+const PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Take a name on the session bus and answer calls from it in the background, and signal changes
+/// to the status.
+pub fn serve(commands: Sender<Command>, status: SharedStatus) -> io::Result<()> {
+    let connection = Connection::session()?;
+    // Another instance may have the plain name
+    connection
+        .request_name(NAME)
+        .or_else(|_| connection.request_name(&format!("{NAME}.instance{}", process::id())))?;
+
+    let calls_status = status.clone();
+    connection.serve(move |call| answer(call, &commands, &calls_status));
+
+    thread::spawn(move || {
+        let mut shown = None;
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let (playback_status, metadata) = {
+                let Ok(status) = status.lock() else {
+                    return;
+                };
+                (playback_status(&status), metadata(&status))
+            };
+            if shown.as_ref() == Some(&(playback_status.clone(), metadata.clone())) {
+                continue;
+            }
+            let changed = Message::properties_changed(
+                PATH,
+                PLAYER,
+                vec![
+                    ("PlaybackStatus", playback_status.clone()),
+                    ("Metadata", metadata.clone()),
+                ],
+            );
+            if connection.send(changed).is_err() {
+                return;
+            }
+            shown = Some((playback_status, metadata));
+        }
+    });
+    Ok(())
+}
+
+const INTROSPECTION: &str = r#"<node>
+  <interface name="org.mpris.MediaPlayer2">
+    <method name="Raise"/>
+    <method name="Quit"/>
+    <property name="CanQuit" type="b" access="read"/>
+    <property name="CanRaise" type="b" access="read"/>
+    <property name="HasTrackList" type="b" access="read"/>
+    <property name="Identity" type="s" access="read"/>
+    <property name="SupportedUriSchemes" type="as" access="read"/>
+    <property name="SupportedMimeTypes" type="as" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="Next"/>
+    <method name="Previous"/>
+    <method name="Pause"/>
+    <method name="PlayPause"/>
+    <method name="Stop"/>
+    <method name="Play"/>
+    <method name="Seek"><arg name="Offset" type="x" direction="in"/></method>
+    <method name="SetPosition">
+      <arg name="TrackId" type="o" direction="in"/>
+      <arg name="Position" type="x" direction="in"/>
+    </method>
+    <method name="OpenUri"><arg name="Uri" type="s" direction="in"/></method>
+    <signal name="Seeked"><arg name="Position" type="x"/></signal>
+    <property name="PlaybackStatus" type="s" access="read"/>
+    <property name="Rate" type="d" access="read"/>
+    <property name="Metadata" type="a{sv}" access="read"/>
+    <property name="Position" type="x" access="read"/>
+    <property name="MinimumRate" type="d" access="read"/>
+    <property name="MaximumRate" type="d" access="read"/>
+    <property name="CanGoNext" type="b" access="read"/>
+    <property name="CanGoPrevious" type="b" access="read"/>
+    <property name="CanPlay" type="b" access="read"/>
+    <property name="CanPause" type="b" access="read"/>
+    <property name="CanSeek" type="b" access="read"/>
+    <property name="CanControl" type="b" access="read"/>
+  </interface>
+</node>"#;
+
+/// The reply to a call to one of our objects, `None` for anything else.
+fn answer(call: &Message, commands: &Sender<Command>, status: &SharedStatus) -> Option<Message> {
+    if call.path.as_deref() != Some(PATH) {
+        return None;
+    }
+    let status = status.lock().ok()?.clone();
+    let command = match call.interface.as_deref()? {
+        PLAYER => match call.member.as_deref()? {
+            "Play" => Some(Command::Resume),
+            // Stop would quit the timer, which a media key shouldn't
+            "Pause" | "Stop" => Some(Command::Pause),
+            "PlayPause" if status.as_ref().is_some_and(|status| status.paused) => {
+                Some(Command::Resume)
+            }
+            "PlayPause" => Some(Command::Pause),
+            "Next" => Some(Command::Skip),
+            // There's nothing to go back to or seek in
+            _ => None,
+        },
+        PROPERTIES => {
+            return Some(Message::properties_reply(call, |interface| {
+                properties(interface, &status)
+            }))
+        }
+        INTROSPECTABLE => {
+            return Some(Message::method_return(
+                call,
+                vec![Value::str(INTROSPECTION)],
+            ))
+        }
+        ROOT | PEER => None,
+        _ => {
+            return Some(Message::error(
+                call,
+                "org.freedesktop.DBus.Error.UnknownInterface",
+                "No such interface",
+            ))
+        }
+    };
+    if let Some(command) = command {
+        let _ = commands.send(command);
+    }
+    Some(Message::method_return(call, Vec::new()))
+}
+
+fn properties(interface: &str, status: &Option<Status>) -> Vec<(&'static str, Value)> {
+    match interface {
+        ROOT => vec![
+            ("CanQuit", Value::Bool(false)),
+            ("CanRaise", Value::Bool(false)),
+            ("HasTrackList", Value::Bool(false)),
+            ("Identity", Value::str("pomors")),
+            ("SupportedUriSchemes", Value::strings(&[])),
+            ("SupportedMimeTypes", Value::strings(&[])),
+        ],
+        PLAYER => {
+            let position = status.as_ref().map_or(0, |status| {
+                status.length_secs.saturating_sub(status.remaining_secs)
+            });
+            vec![
+                ("PlaybackStatus", playback_status(status)),
+                ("Rate", Value::F64(1.0)),
+                ("Metadata", metadata(status)),
+                ("Position", Value::I64(position as i64 * 1_000_000)),
+                ("MinimumRate", Value::F64(1.0)),
+                ("MaximumRate", Value::F64(1.0)),
+                ("CanGoNext", Value::Bool(true)),
+                ("CanGoPrevious", Value::Bool(false)),
+                ("CanPlay", Value::Bool(true)),
+                ("CanPause", Value::Bool(true)),
+                ("CanSeek", Value::Bool(false)),
+                ("CanControl", Value::Bool(true)),
+            ]
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn playback_status(status: &Option<Status>) -> Value {
+    Value::str(match status {
+        None => "Stopped",
+        Some(status) if status.paused => "Paused",
+        Some(_) => "Playing",
+    })
+}
+
+/// The period as a track. The album is the time left, to the minute, for widgets that only
+/// show text.
+fn metadata(status: &Option<Status>) -> Value {
+    let Some(status) = status else {
+        return Value::dict([(
+            "mpris:trackid",
+            Value::ObjectPath("/org/mpris/MediaPlayer2/TrackList/NoTrack".into()),
+        )]);
+    };
+    let (period, track) = match status.state {
+        AppState::Working => ("Pomodoro", "/org/pomors/pomodoro"),
+        AppState::TakingABreak => ("Break", "/org/pomors/break"),
+    };
+    let minutes_left = status.remaining_secs.div_ceil(60);
+    Value::dict([
+        ("mpris:trackid", Value::ObjectPath(track.into())),
+        (
+            "mpris:length",
+            Value::I64(status.length_secs as i64 * 1_000_000),
+        ),
+        (
+            "xesam:title",
+            Value::Str(status.task.clone().unwrap_or_else(|| period.to_string())),
+        ),
+        ("xesam:artist", Value::strings(&[period])),
+        (
+            "xesam:album",
+            Value::Str(format!("{minutes_left} min left")),
+        ),
+    ])
+}
@@ -0,0 +1,273 @@
+//! Publishing the timer to an MQTT broker, with Home Assistant discovery, so home automation can
+//! follow it, e.g. to turn lights red while working.
+//!
+//! Only what's needed to publish is implemented, over MQTT 3.1.1 without TLS. Messages are
+//! retained so new subscribers get the current state, and the broker says pomors is offline when
+//! the connection drops.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    process,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{control::Status, timer::AppState};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+/// Time left is sent at most this often, unless something else changed
+const REMAINING_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mqtt {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// What topics start with
+    #[serde(default = "default_topic")]
+    pub topic: String,
+    /// Announce the sensors to Home Assistant
+    #[serde(default = "default_discovery")]
+    pub discovery: bool,
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_topic() -> String {
+    "pomors".to_string()
+}
+
+fn default_discovery() -> bool {
+    true
+}
+
+/// What's published, to send only what changed.
+#[derive(Clone, PartialEq)]
+struct Published {
+    state: &'static str,
+    paused: bool,
+    task: String,
+    remaining_secs: u64,
+}
+
+/// Publishes the status from a thread of its own, connecting again when the connection breaks.
+pub struct Publisher {
+    statuses: Sender<Status>,
+}
+
+impl Publisher {
+    pub fn start(mqtt: &Mqtt) -> Publisher {
+        let (statuses, received) = mpsc::channel::<Status>();
+        let mqtt = mqtt.clone();
+        thread::spawn(move || {
+            let mut connection: Option<TcpStream> = None;
+            let mut published: Option<(Published, Instant)> = None;
+            let mut last_sent = Instant::now();
+            loop {
+                let status = match received.recv_timeout(KEEP_ALIVE / 2) {
+                    Ok(status) => Some(status),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                };
+                if connection.is_none() {
+                    connection = mqtt.connect().ok();
+                    published = None;
+                }
+                let Some(stream) = &mut connection else {
+                    continue;
+                };
+                let sent = match status {
+                    Some(status) => mqtt.publish_status(stream, &status, &mut published),
+                    None if last_sent.elapsed() >= KEEP_ALIVE / 2 => {
+                        stream.write_all(&[0xC0, 0]).map(|()| true)
+                    }
+                    None => Ok(false),
+                };
+                match sent {
+                    Ok(true) => last_sent = Instant::now(),
+                    Ok(false) => {}
+                    Err(_) => connection = None,
+                }
+            }
+        });
+        Publisher { statuses }
+    }
+
+    pub fn update(&self, status: Status) {
+        let _ = self.statuses.send(status);
+    }
+}
+
+impl Mqtt {
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{name}", self.topic)
+    }
+
+    /// Connect, announce the sensors and say pomors is online.
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+        let mut flags = 0x02 | 0x04 | 0x20; // Clean session and a retained will
+        let mut payload = string(&format!("pomors-{}", process::id()));
+        payload.extend(string(&self.topic("availability")));
+        payload.extend(string("offline"));
+        if let Some(username) = &self.username {
+            flags |= 0x80;
+            payload.extend(string(username));
+        }
+        if let Some(password) = &self.password {
+            flags |= 0x40;
+            payload.extend(string(password));
+        }
+        let mut body = string("MQTT");
+        body.push(4); // 3.1.1
+        body.push(flags);
+        body.extend((KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+        body.extend(payload);
+        stream.write_all(&packet(0x10, &body))?;
+
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("the broker refused the connection ({})", connack[3]),
+            ));
+        }
+        // Only ping responses come back, which aren't needed
+        let mut reader = stream.try_clone()?;
+        reader.set_read_timeout(None)?;
+        thread::spawn(move || {
+            let mut buf = [0; 64];
+            while matches!(reader.read(&mut buf), Ok(n) if n > 0) {}
+        });
+
+        if self.discovery {
+            self.announce(&mut stream)?;
+        }
+        publish(&mut stream, &self.topic("availability"), "online")?;
+        Ok(stream)
+    }
+
+    /// Send Home Assistant the config of each sensor.
+    fn announce(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let device = json!({ "identifiers": [self.topic], "name": "pomors", "model": "pomors" });
+        let sensors = [
+            ("sensor", "state", "State", json!({ "icon": "mdi:timer" })),
+            (
+                "sensor",
+                "remaining",
+                "Time left",
+                json!({ "unit_of_measurement": "s", "device_class": "duration" }),
+            ),
+            (
+                "sensor",
+                "task",
+                "Task",
+                json!({ "icon": "mdi:format-list-checks" }),
+            ),
+            ("binary_sensor", "paused", "Paused", json!({})),
+        ];
+        for (component, name, title, mut config) in sensors {
+            config["name"] = json!(title);
+            config["unique_id"] = json!(format!("{}_{name}", self.topic));
+            config["state_topic"] = json!(self.topic(name));
+            config["availability_topic"] = json!(self.topic("availability"));
+            config["device"] = device.clone();
+            let topic = format!(
+                "homeassistant/{component}/{}/{name}/config",
+                self.topic.replace('/', "_")
+            );
+            publish(stream, &topic, &config.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Publish what changed in `status`. Whether anything was sent.
+    fn publish_status(
+        &self,
+        stream: &mut TcpStream,
+        status: &Status,
+        published: &mut Option<(Published, Instant)>,
+    ) -> io::Result<bool> {
+        let current = Published {
+            state: match status.state {
+                AppState::Working => "working",
+                AppState::TakingABreak if status.long_break => "long_break",
+                AppState::TakingABreak => "short_break",
+            },
+            paused: status.paused,
+            task: status.task.clone().unwrap_or_default(),
+            remaining_secs: status.remaining_secs,
+        };
+        let last = published.as_ref().map(|(last, _)| last);
+        let mut sent = false;
+        if last.map(|last| last.state) != Some(current.state) {
+            publish(stream, &self.topic("state"), current.state)?;
+            sent = true;
+        }
+        if last.map(|last| last.paused) != Some(current.paused) {
+            let paused = if current.paused { "ON" } else { "OFF" };
+            publish(stream, &self.topic("paused"), paused)?;
+            sent = true;
+        }
+        if last.map(|last| &last.task) != Some(&current.task) {
+            publish(stream, &self.topic("task"), &current.task)?;
+            sent = true;
+        }
+        let remaining_due = published
+            .as_ref()
+            .is_none_or(|(_, at)| at.elapsed() >= REMAINING_INTERVAL);
+        if sent || remaining_due {
+            let remaining = current.remaining_secs.to_string();
+            publish(stream, &self.topic("remaining"), &remaining)?;
+            *published = Some((current, Instant::now()));
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+/// A retained message at the lowest quality of service.
+fn publish(stream: &mut TcpStream, topic: &str, message: &str) -> io::Result<()> {
+    let mut body = string(topic);
+    body.extend(message.as_bytes());
+    stream.write_all(&packet(0x31, &body))
+}
+
+/// A length-prefixed UTF-8 string.
+fn string(text: &str) -> Vec<u8> {
+    let mut bytes = (text.len() as u16).to_be_bytes().to_vec();
+    bytes.extend(text.as_bytes());
+    bytes
+}
+
+/// A packet of `kind`, with the length of `body` encoded seven bits at a time.
+fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![kind];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
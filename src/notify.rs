@@ -0,0 +1,85 @@
+//! Fallback alerts for when there's no sound to play.
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::mpsc::Sender,
+    thread,
+};
+
+use crate::control;
+
+/// A button on a notification, sending `command` back to the app when clicked.
+pub struct Action {
+    pub label: &'static str,
+    pub command: control::Command,
+}
+
+/// Ring the terminal bell.
+pub fn bell() {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
+
+/// Show a desktop notification, if the platform has a way to.
+pub fn desktop(summary: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.args([
+            "-e",
+            &format!("display notification {body:?} with title {summary:?}"),
+        ]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args([summary, body]);
+        command
+    };
+
+    // Don't wait for the notification, it can take a while to show up, but reap it once it has
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Ok(mut child) = child {
+        thread::spawn(move || child.wait());
+    }
+}
+
+/// Show a desktop notification with buttons, on platforms that support them. Otherwise it's a
+/// plain notification.
+pub fn desktop_with_actions(
+    summary: &str,
+    body: &str,
+    actions: Vec<Action>,
+    commands: Sender<control::Command>,
+) {
+    if cfg!(target_os = "macos") || actions.is_empty() {
+        return desktop(summary, body);
+    }
+
+    let mut command = Command::new("notify-send");
+    command.arg("--wait");
+    for (i, action) in actions.iter().enumerate() {
+        command.arg(format!("--action={i}={}", action.label));
+    }
+    command.args([summary, body]);
+
+    // notify-send prints the picked action once the notification is dismissed
+    thread::spawn(move || {
+        let Ok(output) = command.stdin(Stdio::null()).stderr(Stdio::null()).output() else {
+            return;
+        };
+        let picked = String::from_utf8_lossy(&output.stdout);
+        if let Some(action) = picked
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| actions.get(i))
+        {
+            let _ = commands.send(action.command.clone());
+        }
+    });
+}
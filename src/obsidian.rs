@@ -0,0 +1,47 @@
+//! Logging pomodoros in the daily note of an [Obsidian](https://obsidian.md) vault.
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::{format, history::Record};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Obsidian {
+    pub vault: PathBuf,
+    /// Where the daily note is in the vault, as a strftime format
+    #[serde(default = "default_daily_note")]
+    pub daily_note: String,
+}
+
+fn default_daily_note() -> String {
+    "%Y-%m-%d.md".to_string()
+}
+
+impl Obsidian {
+    /// Add a line for `record` to the end of today's note, making the note if it doesn't exist.
+    pub fn log(&self, record: &Record) -> io::Result<()> {
+        let mut name = String::new();
+        write!(name, "{}", Local::now().format(&self.daily_note))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "bad daily_note format"))?;
+        let path = self.vault.join(name);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let time = |time: DateTime<Utc>| time.with_timezone(&Local).format("%H:%M");
+        let mut note = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(
+            note,
+            "- {}–{} 🍅 {} ({})",
+            time(record.start),
+            time(record.end),
+            record.task.as_deref().unwrap_or("Pomodoro"),
+            format::chrono_duration(record.duration())
+        )
+    }
+}
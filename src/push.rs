@@ -0,0 +1,87 @@
+//! Sending transitions to the phone through [Pushover](https://pushover.net) or a
+//! [Telegram](https://core.telegram.org/bots) bot, for when the desk is out of earshot.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{thread, time::Duration};
+
+use crate::timer::Transition;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "service", rename_all = "snake_case")]
+pub enum Service {
+    Pushover {
+        /// The application's API token
+        token: String,
+        /// The user key
+        user: String,
+    },
+    Telegram {
+        bot_token: String,
+        /// The chat with the bot, which it has to have been sent a message in
+        chat_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Push {
+    #[serde(flatten)]
+    pub service: Service,
+    /// Transitions to send
+    #[serde(default = "default_events")]
+    pub events: Vec<Transition>,
+}
+
+fn default_events() -> Vec<Transition> {
+    vec![Transition::BreakStart, Transition::BreakEnd]
+}
+
+/// Send the transition to every service that wants it, without waiting. `end` is when the period
+/// that's starting ends.
+pub fn send(
+    pushes: &[Push],
+    transition: Transition,
+    task: Option<&str>,
+    end: Option<DateTime<Local>>,
+) {
+    let until = end.map(|end| format!(" until {}", end.format("%H:%M")));
+    let until = until.as_deref().unwrap_or_default();
+    let message = match transition {
+        Transition::WorkStart => match task {
+            Some(task) => format!("Working on {task}{until}"),
+            None => format!("Working{until}"),
+        },
+        Transition::WorkEnd => "Pomodoro done".to_string(),
+        Transition::BreakStart => format!("Time for a break{until}"),
+        Transition::BreakEnd => "The break is over, back to work".to_string(),
+    };
+    for push in pushes
+        .iter()
+        .filter(|push| push.events.contains(&transition))
+    {
+        let service = push.service.clone();
+        let message = message.clone();
+        // A push that doesn't go through is lost, it's only worth sending while it's current
+        thread::spawn(move || {
+            let _ = match service {
+                Service::Pushover { token, user } => {
+                    ureq::post("https://api.pushover.net/1/messages.json")
+                        .timeout(TIMEOUT)
+                        .send_form(&[
+                            ("token", &token),
+                            ("user", &user),
+                            ("title", "pomors"),
+                            ("message", &message),
+                        ])
+                }
+                Service::Telegram { bot_token, chat_id } => ureq::post(&format!(
+                    "https://api.telegram.org/bot{bot_token}/sendMessage"
+                ))
+                .timeout(TIMEOUT)
+                .send_form(&[("chat_id", &chat_id), ("text", &message)]),
+            };
+        });
+    }
+}
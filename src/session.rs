@@ -0,0 +1,67 @@
+//! The state of the running session, saved as it goes so it can be picked up again after an
+//! accidental quit or a reboot.
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{task::Task, timer::AppState};
+
+/// How long ago a session may have been saved for `pomors` to offer to resume it.
+const RECENT: Duration = Duration::from_secs(12 * 60 * 60);
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub saved_at: DateTime<Utc>,
+    pub tasks: Vec<Task>,
+    /// Index into `tasks`
+    pub selected: Option<usize>,
+    pub state: AppState,
+    pub pomodoros_in_cycle: u32,
+    /// Time spent in the current period, not counting pauses
+    pub elapsed: Duration,
+}
+
+impl Session {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// The session saved at `path`, if there is one.
+    pub fn load(path: &Path) -> io::Result<Option<Session>> {
+        match fs::read_to_string(path) {
+            Ok(session) => Ok(Some(serde_json::from_str(&session)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn is_recent(&self) -> bool {
+        (Utc::now() - self.saved_at)
+            .to_std()
+            .is_ok_and(|age| age < RECENT)
+    }
+
+    /// Ask on the terminal whether to resume the session, if it's recent enough to be worth it.
+    pub fn ask_to_resume(&self) -> io::Result<bool> {
+        if !self.is_recent() {
+            return Ok(false);
+        }
+
+        let done = self.tasks.iter().filter(|task| task.is_complete).count();
+        print!(
+            "Resume the session from {}, with {done}/{} tasks done? [y/N] ",
+            self.saved_at.with_timezone(&Local).format("%H:%M"),
+            self.tasks.len()
+        );
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+    }
+}
@@ -0,0 +1,58 @@
+//! Setting the Slack status and snoozing Slack notifications while working.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+const API: &str = "https://slack.com/api";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Slack {
+    /// User token with the `users.profile:write` and `dnd:write` scopes
+    pub token: String,
+    /// Also pause Slack notifications while working
+    #[serde(default = "default_do_not_disturb")]
+    pub do_not_disturb: bool,
+}
+
+fn default_do_not_disturb() -> bool {
+    true
+}
+
+impl Slack {
+    fn post(&self, method: &str) -> ureq::Request {
+        ureq::post(&format!("{API}/{method}"))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .timeout(TIMEOUT)
+    }
+
+    /// Set the status to say we're focusing until `until`, and snooze notifications until then.
+    /// Failures are ignored, Slack just doesn't show it.
+    pub fn focus(&self, until: DateTime<Local>) {
+        let _ = self.post("users.profile.set").send_json(json!({
+            "profile": {
+                "status_text": format!("Focusing until {}", until.format("%H:%M")),
+                "status_emoji": ":tomato:",
+                "status_expiration": until.timestamp(),
+            }
+        }));
+        if self.do_not_disturb {
+            let minutes = (until - Local::now()).num_minutes().max(1);
+            let _ = self
+                .post("dnd.setSnooze")
+                .send_form(&[("num_minutes", &minutes.to_string())]);
+        }
+    }
+
+    /// Clear the status and the snooze.
+    pub fn clear(&self) {
+        let _ = self.post("users.profile.set").send_json(json!({
+            "profile": { "status_text": "", "status_emoji": "", "status_expiration": 0 }
+        }));
+        if self.do_not_disturb {
+            let _ = self.post("dnd.endSnooze").call();
+        }
+    }
+}
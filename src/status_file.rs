@@ -0,0 +1,46 @@
+//! Keeping the status in files, `status.json` and `status.txt` with a line like
+//! `🍅 12:30 Write report`, for tools that can only read files. They're replaced rather than
+//! written in place, so readers never see half of one.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::{self, StatusFormat},
+    control::Status,
+    io::replace,
+};
+
+const JSON_FILE: &str = "status.json";
+const TEXT_FILE: &str = "status.txt";
+
+pub struct StatusFile {
+    dir: PathBuf,
+    /// What's in `status.json`, to only write when it changes
+    written: Option<String>,
+}
+
+impl StatusFile {
+    /// Keep the files in `dir`.
+    pub fn new(dir: &Path) -> StatusFile {
+        StatusFile {
+            dir: dir.to_path_buf(),
+            written: None,
+        }
+    }
+
+    /// Write the files for `status`, `None` when nothing is running.
+    pub fn update(&mut self, status: Option<&Status>) -> io::Result<()> {
+        let json = cli::format_status(status, StatusFormat::Json)?;
+        if self.written.as_ref() == Some(&json) {
+            return Ok(());
+        }
+        let text = cli::format_status(status, StatusFormat::Plain)?;
+        replace(&self.dir.join(JSON_FILE), &format!("{json}\n"))?;
+        replace(&self.dir.join(TEXT_FILE), &format!("{text}\n"))?;
+        self.written = Some(json);
+        Ok(())
+    }
+}
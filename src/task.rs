@@ -0,0 +1,582 @@
+//! The tasks worked on, and the list they're picked from.
+
+use chrono::{DateTime, Utc};
+use ratatui::widgets::ListState;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub name: String,
+    pub is_complete: bool,
+    /// The times the task was worked on, oldest first, not counting the one still going on
+    pub work_periods: Vec<WorkPeriod>,
+    /// When the task was started on, if it's being worked on now
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_since: Option<DateTime<Utc>>,
+    /// Estimated number of pomodoros
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    /// How urgent the task is, 0 being the most urgent: todo.txt's `(A)`, Todoist's p1 or JIRA's
+    /// Highest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    /// Whether the tracked time went over the estimate, and the user was told so. Kept in the
+    /// session so a resumed one doesn't tell them again.
+    #[serde(default)]
+    pub over_estimate: bool,
+    /// The service the task was taken from, to update it there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<Remote>,
+}
+
+/// A stretch of time a task was worked on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkPeriod {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl WorkPeriod {
+    pub fn duration(&self) -> chrono::Duration {
+        self.end - self.start
+    }
+}
+
+/// A task in another service.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Remote {
+    /// The Todoist task ID
+    Todoist(String),
+    GitHub {
+        repo: String,
+        number: u64,
+    },
+    /// The JIRA issue key
+    Jira(String),
+}
+
+impl Task {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            is_complete: false,
+            work_periods: Vec::new(),
+            active_since: None,
+            estimate: None,
+            priority: None,
+            over_estimate: false,
+            remote: None,
+        }
+    }
+
+    /// Create a task from e.g. `(B) Write report ~3`, where the `(B)` is the todo.txt priority and
+    /// the `~3` is the estimated pomodoros. Both are optional.
+    pub fn parse(input: &str) -> Self {
+        let input = input.trim();
+        let (priority, input) = match input.as_bytes() {
+            [b'(', letter @ b'A'..=b'Z', b')', b' ', ..] => {
+                (Some(letter - b'A'), input[4..].trim_start())
+            }
+            _ => (None, input),
+        };
+
+        let mut task = match input.rsplit_once(" ~") {
+            Some((name, estimate)) => match estimate.parse() {
+                Ok(estimate) => {
+                    let mut task = Task::new(name.trim());
+                    task.estimate = Some(estimate);
+                    task
+                }
+                Err(_) => Task::new(input),
+            },
+            None => Task::new(input),
+        };
+        task.priority = priority;
+        task
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active_since.is_some()
+    }
+
+    /// Start working on the task at `now`, unless it's already being worked on.
+    pub fn start_work(&mut self, now: DateTime<Utc>) {
+        self.active_since.get_or_insert(now);
+    }
+
+    /// Stop working on the task at `now`, recording the time since it was started on. Periods
+    /// that took no time aren't kept.
+    pub fn stop_work(&mut self, now: DateTime<Utc>) {
+        if let Some(start) = self.active_since.take() {
+            if now > start {
+                self.work_periods.push(WorkPeriod { start, end: now });
+            }
+        }
+    }
+
+    /// The time spent on the task in the periods that are over.
+    pub fn task_total_duration(&self) -> chrono::Duration {
+        self.work_periods
+            .iter()
+            .fold(chrono::Duration::zero(), |acc, work_period| {
+                acc + work_period.duration()
+            })
+    }
+
+    /// Like `task_total_duration`, but also counting the period currently being worked on, up
+    /// to `now`.
+    pub fn tracked_duration(&self, now: DateTime<Utc>) -> chrono::Duration {
+        let current = self
+            .active_since
+            .map_or(chrono::Duration::zero(), |start| now - start);
+        self.task_total_duration() + current.max(chrono::Duration::zero())
+    }
+}
+
+/// Orders the task list can be shown in. The tasks themselves stay in the order they were given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Manual,
+    /// Least estimated time left first, tasks without estimates last
+    RemainingEstimate,
+    /// Most tracked time first
+    TotalTime,
+    IncompleteFirst,
+    /// Most urgent first, tasks without a priority last
+    Priority,
+}
+
+impl SortOrder {
+    pub fn next(self) -> Self {
+        match self {
+            SortOrder::Manual => SortOrder::RemainingEstimate,
+            SortOrder::RemainingEstimate => SortOrder::TotalTime,
+            SortOrder::TotalTime => SortOrder::IncompleteFirst,
+            SortOrder::IncompleteFirst => SortOrder::Priority,
+            SortOrder::Priority => SortOrder::Manual,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortOrder::Manual => "manual order",
+            SortOrder::RemainingEstimate => "remaining estimate",
+            SortOrder::TotalTime => "total time",
+            SortOrder::IncompleteFirst => "incomplete first",
+            SortOrder::Priority => "priority",
+        }
+    }
+}
+
+pub struct StatefulList {
+    /// Selection among the tasks as shown, in `order`
+    pub state: ListState,
+    pub items: Vec<Task>,
+    pub sort: SortOrder,
+    /// Indices into `items`, in the order they're shown
+    pub order: Vec<usize>,
+    /// Whether time spent on the selected task counts, which it doesn't during breaks
+    pub tracking: bool,
+}
+
+impl StatefulList {
+    pub fn with_items(items: Vec<Task>) -> StatefulList {
+        StatefulList {
+            state: ListState::default(),
+            order: (0..items.len()).collect(),
+            items,
+            sort: SortOrder::Manual,
+            tracking: true,
+        }
+    }
+
+    /// Start or stop counting the time spent on the selected task, and on those selected after it.
+    pub fn set_tracking(&mut self, tracking: bool, now: DateTime<Utc>) {
+        if tracking == self.tracking {
+            return;
+        }
+        self.tracking = tracking;
+        if let Some(selected_task) = self.get_selected_mut() {
+            if tracking {
+                selected_task.start_work(now)
+            } else {
+                selected_task.stop_work(now)
+            }
+        }
+    }
+
+    /// Show the tasks in `sort` order, keeping the same task selected. `pomodoro_length` is
+    /// needed to turn estimates into time.
+    pub fn sort_by(&mut self, sort: SortOrder, pomodoro_length: Duration, now: DateTime<Utc>) {
+        let selected = self.state.selected().map(|i| self.order[i]);
+
+        self.sort = sort;
+        self.order = (0..self.items.len()).collect();
+        let items = &self.items;
+        match sort {
+            SortOrder::Manual => {}
+            SortOrder::RemainingEstimate => self.order.sort_by_key(|&i| {
+                let task = &items[i];
+                task.estimate.map_or(Duration::MAX, |estimate| {
+                    (pomodoro_length * estimate)
+                        .saturating_sub(task.tracked_duration(now).to_std().unwrap_or_default())
+                })
+            }),
+            SortOrder::TotalTime => self
+                .order
+                .sort_by_key(|&i| std::cmp::Reverse(items[i].tracked_duration(now))),
+            SortOrder::IncompleteFirst => self.order.sort_by_key(|&i| items[i].is_complete),
+            SortOrder::Priority => self
+                .order
+                .sort_by_key(|&i| items[i].priority.unwrap_or(u8::MAX)),
+        }
+
+        if let Some(selected) = selected {
+            self.state
+                .select(self.order.iter().position(|&i| i == selected));
+        }
+    }
+
+    pub fn next(&mut self, now: DateTime<Utc>) {
+        if self.order.is_empty() {
+            return;
+        }
+        if let Some(selected_task) = self.get_selected_mut() {
+            selected_task.stop_work(now)
+        }
+
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i + 1 >= self.order.len() {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+        if self.tracking {
+            if let Some(selected_task) = self.get_selected_mut() {
+                selected_task.start_work(now)
+            }
+        }
+    }
+
+    pub fn previous(&mut self, now: DateTime<Utc>) {
+        if self.order.is_empty() {
+            return;
+        }
+        if let Some(selected_task) = self.get_selected_mut() {
+            selected_task.stop_work(now)
+        }
+
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.order.len() - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+
+        if self.tracking {
+            if let Some(selected_task) = self.get_selected_mut() {
+                selected_task.start_work(now)
+            }
+        }
+    }
+
+    /// Add `task` to the end of the list, whatever order it's shown in.
+    pub fn push(&mut self, task: Task) {
+        self.order.push(self.items.len());
+        self.items.push(task);
+    }
+
+    /// Select the first incomplete task after the selected one, wrapping around, if any.
+    pub fn select_next_incomplete(&mut self, now: DateTime<Utc>) {
+        let selected = self.state.selected().unwrap_or_default();
+        let count = self.order.len();
+        let next = (1..count)
+            .map(|offset| (selected + offset) % count)
+            .find(|&i| !self.items[self.order[i]].is_complete);
+        if let Some(next) = next {
+            self.select(next, now);
+        }
+    }
+
+    pub fn select(&mut self, i: usize, now: DateTime<Utc>) {
+        if i >= self.order.len() || self.state.selected() == Some(i) {
+            return;
+        }
+
+        if let Some(selected_task) = self.get_selected_mut() {
+            selected_task.stop_work(now)
+        }
+        self.state.select(Some(i));
+        if self.tracking {
+            if let Some(selected_task) = self.get_selected_mut() {
+                selected_task.start_work(now)
+            }
+        }
+    }
+
+    pub fn unselect(&mut self, now: DateTime<Utc>) {
+        if let Some(selected_task) = self.get_selected_mut() {
+            selected_task.stop_work(now)
+        }
+        self.state.select(None);
+    }
+
+    pub fn get_selected_mut(&mut self) -> Option<&mut Task> {
+        let item = *self.order.get(self.state.selected()?)?;
+        self.items.get_mut(item)
+    }
+
+    pub fn get_selected(&self) -> Option<&Task> {
+        let item = *self.order.get(self.state.selected()?)?;
+        self.items.get(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn list(count: usize) -> StatefulList {
+        StatefulList::with_items((0..count).map(|i| Task::new(&i.to_string())).collect())
+    }
+
+    /// The selection is of a task in the list, the list shows every task once, only the selected
+    /// task is being worked on, and no two periods overlap.
+    fn assert_consistent(list: &StatefulList) {
+        assert!(list.state.selected().is_none_or(|i| i < list.order.len()));
+        let mut shown = list.order.clone();
+        shown.sort_unstable();
+        assert!(shown.iter().copied().eq(0..list.items.len()));
+        for (position, &i) in list.order.iter().enumerate() {
+            let selected = list.state.selected() == Some(position);
+            assert_eq!(list.items[i].is_active(), selected && list.tracking);
+        }
+        let mut periods: Vec<_> = (list.items.iter())
+            .flat_map(|task| task.work_periods.iter())
+            .collect();
+        periods.sort_by_key(|period| period.start);
+        assert!(periods.iter().all(|period| period.end > period.start));
+        assert!(periods.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    }
+
+    #[test]
+    fn records_the_time_between_starting_and_stopping() {
+        let mut task = Task::new("task");
+        task.start_work(at(0));
+        assert_eq!(task.tracked_duration(at(60)), chrono::Duration::seconds(60));
+        task.stop_work(at(90));
+        assert!(!task.is_active());
+        assert_eq!(
+            task.work_periods,
+            vec![WorkPeriod {
+                start: at(0),
+                end: at(90)
+            }]
+        );
+        assert_eq!(task.task_total_duration(), chrono::Duration::seconds(90));
+        assert_eq!(
+            task.tracked_duration(at(200)),
+            chrono::Duration::seconds(90)
+        );
+    }
+
+    #[test]
+    fn starting_again_keeps_the_first_start() {
+        let mut task = Task::new("task");
+        task.start_work(at(0));
+        task.start_work(at(30));
+        task.stop_work(at(60));
+        assert_eq!(task.work_periods.len(), 1);
+        assert_eq!(task.task_total_duration(), chrono::Duration::seconds(60));
+    }
+
+    #[test]
+    fn stopping_when_not_started_does_nothing() {
+        let mut task = Task::new("task");
+        task.stop_work(at(0));
+        task.start_work(at(10));
+        task.stop_work(at(20));
+        task.stop_work(at(30));
+        assert_eq!(task.task_total_duration(), chrono::Duration::seconds(10));
+    }
+
+    #[test]
+    fn periods_that_took_no_time_are_dropped() {
+        let mut task = Task::new("task");
+        task.start_work(at(10));
+        task.stop_work(at(10));
+        // The clock went back
+        task.start_work(at(10));
+        task.stop_work(at(5));
+        assert!(task.work_periods.is_empty());
+        assert!(!task.is_active());
+    }
+
+    #[test]
+    fn rapid_switching_keeps_one_task_active() {
+        let mut list = list(3);
+        list.next(at(0));
+        assert_consistent(&list);
+        for step in 0..200 {
+            // Several switches a second, some taking no time at all
+            let now = at(step / 3);
+            match step % 5 {
+                0 | 1 => list.next(now),
+                2 => list.previous(now),
+                3 => list.select(step as usize % 4, now),
+                _ => list.select_next_incomplete(now),
+            }
+            assert_consistent(&list);
+        }
+        list.unselect(at(100));
+        assert_consistent(&list);
+        assert!(list.items.iter().all(|task| !task.is_active()));
+    }
+
+    #[test]
+    fn switching_tasks_while_not_tracking_starts_none() {
+        let mut list = list(3);
+        list.next(at(0));
+        list.set_tracking(false, at(10));
+        assert_consistent(&list);
+        list.next(at(20));
+        list.previous(at(30));
+        list.select(2, at(40));
+        assert_consistent(&list);
+        list.set_tracking(true, at(50));
+        assert!(list.get_selected().unwrap().is_active());
+        assert_consistent(&list);
+        assert_eq!(
+            list.items[0].task_total_duration(),
+            chrono::Duration::seconds(10)
+        );
+    }
+
+    #[test]
+    fn parses_the_priority_and_estimate() {
+        let task = Task::parse("(B) Write report ~3");
+        assert_eq!(task.name, "Write report");
+        assert_eq!(task.priority, Some(1));
+        assert_eq!(task.estimate, Some(3));
+
+        let task = Task::parse("(b) Write report");
+        assert_eq!(task.name, "(b) Write report");
+        assert_eq!(task.priority, None);
+    }
+
+    #[test]
+    fn sorting_by_priority_puts_tasks_without_one_last() {
+        let mut list = StatefulList::with_items(
+            ["none", "(C) low", "(A) high", "(B) middle"]
+                .into_iter()
+                .map(Task::parse)
+                .collect(),
+        );
+        list.select(0, at(0));
+        list.sort_by(SortOrder::Priority, Duration::from_secs(25 * 60), at(0));
+        let names: Vec<_> = (list.order.iter())
+            .map(|&i| list.items[i].name.as_str())
+            .collect();
+        assert_eq!(names, ["high", "middle", "low", "none"]);
+        assert_eq!(list.get_selected().unwrap().name, "none");
+    }
+
+    #[test]
+    fn navigating_an_empty_list_selects_nothing() {
+        let mut list = list(0);
+        list.next(at(0));
+        list.previous(at(0));
+        list.select(0, at(0));
+        list.select_next_incomplete(at(0));
+        assert_eq!(list.state.selected(), None);
+        assert!(list.get_selected().is_none());
+    }
+
+    /// A xorshift generator, so the random tests are the same on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    #[test]
+    fn random_navigation_keeps_the_list_consistent() {
+        for seed in 1..=500 {
+            let mut rng = Rng(seed);
+            let mut list = list(rng.below(6) as usize);
+            let mut secs = 0;
+            let mut done = Vec::new();
+            for _ in 0..100 {
+                // Sometimes no time passes between steps
+                secs += rng.below(3) as i64;
+                let now = at(secs);
+                let step = match rng.below(9) {
+                    0 => {
+                        list.next(now);
+                        "next"
+                    }
+                    1 => {
+                        list.previous(now);
+                        "previous"
+                    }
+                    2 => {
+                        list.unselect(now);
+                        "unselect"
+                    }
+                    3 => {
+                        list.select(rng.below(8) as usize, now);
+                        "select"
+                    }
+                    4 => {
+                        list.select_next_incomplete(now);
+                        "select_next_incomplete"
+                    }
+                    5 => {
+                        list.push(Task::new("new"));
+                        "push"
+                    }
+                    6 => {
+                        let tracking = !list.tracking;
+                        list.set_tracking(tracking, now);
+                        "set_tracking"
+                    }
+                    7 => {
+                        let sort = list.sort.next();
+                        list.sort_by(sort, Duration::from_secs(25 * 60), now);
+                        "sort_by"
+                    }
+                    _ => {
+                        if let Some(task) = list.get_selected_mut() {
+                            task.is_complete = !task.is_complete;
+                        }
+                        "toggle complete"
+                    }
+                };
+                done.push(step);
+                let result = std::panic::catch_unwind(|| assert_consistent(&list));
+                assert!(result.is_ok(), "seed {seed}, after {done:?}");
+            }
+        }
+    }
+}
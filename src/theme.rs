@@ -0,0 +1,130 @@
+//! Colors used by the UI. Widgets take their colors from a `Theme` rather than hardcoding them.
+
+use clap::ValueEnum;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// The timer while working
+    pub working: Color,
+    /// The timer while taking a break
+    pub taking_a_break: Color,
+    pub complete_task: Color,
+    pub incomplete_task: Color,
+    /// Tasks that took longer than estimated
+    pub over_estimate: Color,
+    /// Screens not tied to the timer, like the planner and help
+    pub accent: Color,
+    /// Secondary information
+    pub muted: Color,
+    /// Also show state with symbols and text styles, for when the colors can't be told apart
+    pub symbols: bool,
+}
+
+impl Theme {
+    pub const DEFAULT: Theme = Theme {
+        working: Color::Red,
+        taking_a_break: Color::Green,
+        complete_task: Color::Green,
+        incomplete_task: Color::Red,
+        over_estimate: Color::Yellow,
+        accent: Color::LightBlue,
+        muted: Color::DarkGray,
+        symbols: false,
+    };
+
+    pub const GRUVBOX: Theme = Theme {
+        working: Color::Rgb(0xfb, 0x49, 0x34),
+        taking_a_break: Color::Rgb(0xb8, 0xbb, 0x26),
+        complete_task: Color::Rgb(0xb8, 0xbb, 0x26),
+        incomplete_task: Color::Rgb(0xfb, 0x49, 0x34),
+        over_estimate: Color::Rgb(0xfa, 0xbd, 0x2f),
+        accent: Color::Rgb(0x83, 0xa5, 0x98),
+        muted: Color::Rgb(0x92, 0x83, 0x74),
+        symbols: false,
+    };
+
+    pub const SOLARIZED_DARK: Theme = Theme {
+        working: Color::Rgb(0xdc, 0x32, 0x2f),
+        taking_a_break: Color::Rgb(0x85, 0x99, 0x00),
+        complete_task: Color::Rgb(0x85, 0x99, 0x00),
+        incomplete_task: Color::Rgb(0xdc, 0x32, 0x2f),
+        over_estimate: Color::Rgb(0xb5, 0x89, 0x00),
+        accent: Color::Rgb(0x26, 0x8b, 0xd2),
+        muted: Color::Rgb(0x58, 0x6e, 0x75),
+        symbols: false,
+    };
+
+    /// For terminals with a light background
+    pub const SOLARIZED_LIGHT: Theme = Theme {
+        muted: Color::Rgb(0x93, 0xa1, 0xa1),
+        ..Theme::SOLARIZED_DARK
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        working: Color::LightRed,
+        taking_a_break: Color::LightGreen,
+        complete_task: Color::LightGreen,
+        incomplete_task: Color::White,
+        over_estimate: Color::LightYellow,
+        accent: Color::LightCyan,
+        muted: Color::Gray,
+        symbols: false,
+    };
+
+    /// Blue and orange from the Okabe-Ito palette instead of red and green
+    pub const COLORBLIND: Theme = Theme {
+        working: Color::Rgb(0xe6, 0x9f, 0x00),
+        taking_a_break: Color::Rgb(0x56, 0xb4, 0xe9),
+        complete_task: Color::Rgb(0x00, 0x72, 0xb2),
+        incomplete_task: Color::Rgb(0xe6, 0x9f, 0x00),
+        over_estimate: Color::Rgb(0xd5, 0x5e, 0x00),
+        accent: Color::Rgb(0x56, 0xb4, 0xe9),
+        muted: Color::Gray,
+        symbols: true,
+    };
+
+    /// The terminal's own colors, see <https://no-color.org>
+    pub const NO_COLOR: Theme = Theme {
+        working: Color::Reset,
+        taking_a_break: Color::Reset,
+        complete_task: Color::Reset,
+        incomplete_task: Color::Reset,
+        over_estimate: Color::Reset,
+        accent: Color::Reset,
+        muted: Color::Reset,
+        symbols: true,
+    };
+}
+
+/// The built in themes, as chosen in the config or on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    Default,
+    Gruvbox,
+    SolarizedDark,
+    SolarizedLight,
+    HighContrast,
+    Colorblind,
+}
+
+impl ThemeName {
+    pub fn theme(&self) -> Theme {
+        match self {
+            ThemeName::Default => Theme::DEFAULT,
+            ThemeName::Gruvbox => Theme::GRUVBOX,
+            ThemeName::SolarizedDark => Theme::SOLARIZED_DARK,
+            ThemeName::SolarizedLight => Theme::SOLARIZED_LIGHT,
+            ThemeName::HighContrast => Theme::HIGH_CONTRAST,
+            ThemeName::Colorblind => Theme::COLORBLIND,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DEFAULT
+    }
+}
@@ -0,0 +1,246 @@
+//! Sending each pomodoro to [Toggl Track](https://toggl.com/track/) or
+//! [Clockify](https://clockify.me) as a time entry. Entries are queued in a file and sent from a
+//! thread of their own, so they wait there while the service can't be reached and are retried
+//! later, in this session or the next.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait before trying queued entries again
+const RETRY: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "service", rename_all = "snake_case")]
+pub enum TimeTracking {
+    Toggl {
+        /// API token, from the Profile settings
+        token: String,
+        workspace_id: u64,
+        #[serde(default)]
+        project_id: Option<u64>,
+    },
+    Clockify {
+        /// API key, from the Profile settings
+        api_key: String,
+        workspace_id: String,
+        #[serde(default)]
+        project_id: Option<String>,
+    },
+}
+
+/// A pomodoro to send.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub description: String,
+    pub tags: Vec<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Entry {
+    /// The entry for a pomodoro on `task`. Words of it starting with `#`, `+` or `@` are taken as
+    /// tags, and the rest is the description.
+    pub fn new(task: Option<&str>, start: DateTime<Utc>, end: DateTime<Utc>) -> Entry {
+        let (tags, words): (Vec<&str>, Vec<&str>) = task
+            .unwrap_or_default()
+            .split_whitespace()
+            .partition(|word| {
+                word.len() > 1
+                    && word.starts_with(['#', '+', '@'])
+                    && !word[1..].chars().all(|c| c.is_ascii_digit())
+            });
+        Entry {
+            description: if words.is_empty() {
+                "Pomodoro".to_string()
+            } else {
+                words.join(" ")
+            },
+            tags: tags.iter().map(|tag| tag[1..].to_string()).collect(),
+            start,
+            end,
+        }
+    }
+}
+
+/// Why an entry wasn't sent.
+enum Failure {
+    /// The service turned it down, so sending it again won't help
+    Rejected,
+    /// The service couldn't be reached, or had trouble
+    Retry,
+}
+
+impl From<ureq::Error> for Failure {
+    fn from(e: ureq::Error) -> Failure {
+        match e {
+            ureq::Error::Status(429, _) => Failure::Retry,
+            ureq::Error::Status(400..=499, _) => Failure::Rejected,
+            _ => Failure::Retry,
+        }
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(_: io::Error) -> Failure {
+        Failure::Retry
+    }
+}
+
+/// Sends the entries in the queue file from a thread of its own.
+pub struct Tracker {
+    queue: PathBuf,
+    /// Held while the queue file is read or written
+    lock: Arc<Mutex<()>>,
+    wake: Sender<()>,
+}
+
+impl Tracker {
+    /// Start sending, beginning with what's left in `queue` from before.
+    pub fn start(service: &TimeTracking, queue: PathBuf) -> Tracker {
+        let (wake, woken) = mpsc::channel();
+        let lock = Arc::new(Mutex::new(()));
+        let service = service.clone();
+        let (thread_queue, thread_lock) = (queue.clone(), lock.clone());
+        thread::spawn(move || loop {
+            let queued = match thread_lock.lock() {
+                Ok(_guard) => load(&thread_queue),
+                Err(_) => return,
+            };
+            let done: Vec<&Entry> = queued
+                .iter()
+                .filter(|entry| !matches!(service.send(entry), Err(Failure::Retry)))
+                .collect();
+            let left = match thread_lock.lock() {
+                Ok(_guard) => {
+                    // Entries may have been queued while sending
+                    let mut left = load(&thread_queue);
+                    left.retain(|entry| !done.contains(&entry));
+                    let _ = save(&thread_queue, &left);
+                    left
+                }
+                Err(_) => return,
+            };
+            let woken = if left.is_empty() {
+                woken.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                woken.recv_timeout(RETRY)
+            };
+            if woken == Err(RecvTimeoutError::Disconnected) {
+                return;
+            }
+        });
+        Tracker { queue, lock, wake }
+    }
+
+    /// Queue `entry`, and send it if the service can be reached. It's on disk by the time this
+    /// returns, so quitting doesn't lose it.
+    pub fn push(&self, entry: Entry) {
+        if let Ok(_guard) = self.lock.lock() {
+            let mut queued = load(&self.queue);
+            queued.push(entry);
+            let _ = save(&self.queue, &queued);
+        }
+        let _ = self.wake.send(());
+    }
+}
+
+fn load(queue: &Path) -> Vec<Entry> {
+    fs::read_to_string(queue)
+        .ok()
+        .and_then(|queued| serde_json::from_str(&queued).ok())
+        .unwrap_or_default()
+}
+
+fn save(queue: &Path, entries: &[Entry]) -> io::Result<()> {
+    if entries.is_empty() {
+        match fs::remove_file(queue) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    } else {
+        fs::write(queue, serde_json::to_string_pretty(entries)?)
+    }
+}
+
+impl TimeTracking {
+    fn send(&self, entry: &Entry) -> Result<(), Failure> {
+        match self {
+            TimeTracking::Toggl {
+                token,
+                workspace_id,
+                project_id,
+            } => {
+                let authorization = STANDARD.encode(format!("{token}:api_token"));
+                ureq::post(&format!(
+                    "https://api.track.toggl.com/api/v9/workspaces/{workspace_id}/time_entries"
+                ))
+                .set("Authorization", &format!("Basic {authorization}"))
+                .timeout(TIMEOUT)
+                .send_json(json!({
+                    "created_with": "pomors",
+                    "workspace_id": workspace_id,
+                    "project_id": project_id,
+                    "description": entry.description,
+                    "tags": entry.tags,
+                    "start": entry.start,
+                    "stop": entry.end,
+                    "duration": (entry.end - entry.start).num_seconds(),
+                }))?;
+            }
+            TimeTracking::Clockify {
+                api_key,
+                workspace_id,
+                project_id,
+            } => {
+                let api = format!("https://api.clockify.me/api/v1/workspaces/{workspace_id}");
+                let request = |method: &str, path: &str| {
+                    ureq::request(method, &format!("{api}{path}"))
+                        .set("X-Api-Key", api_key)
+                        .timeout(TIMEOUT)
+                };
+                // Clockify takes tags by ID, so they're looked up, and made if they're new
+                let mut tag_ids = Vec::new();
+                if !entry.tags.is_empty() {
+                    let existing: Vec<Value> = request("GET", "/tags")
+                        .query("page-size", "5000")
+                        .call()?
+                        .into_json()?;
+                    for tag in &entry.tags {
+                        let found = existing.iter().find(|existing| existing["name"] == *tag);
+                        let id = match found {
+                            Some(found) => found["id"].clone(),
+                            None => {
+                                let made: Value = request("POST", "/tags")
+                                    .send_json(json!({ "name": tag }))?
+                                    .into_json()?;
+                                made["id"].clone()
+                            }
+                        };
+                        tag_ids.push(id);
+                    }
+                }
+                request("POST", "/time-entries").send_json(json!({
+                    "description": entry.description,
+                    "projectId": project_id,
+                    "tagIds": tag_ids,
+                    "start": entry.start,
+                    "end": entry.end,
+                }))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,448 @@
+//! The periods the timer goes through, and fitting them into the time there is.
+
+use chrono::{DateTime, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// When the `last_minute` event is printed.
+pub const LAST_MINUTE: Duration = Duration::from_secs(60);
+
+/// The moments in a session that can be reported to webhooks and hooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    WorkStart,
+    WorkEnd,
+    BreakStart,
+    BreakEnd,
+}
+
+impl Transition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transition::WorkStart => "work_start",
+            Transition::WorkEnd => "work_end",
+            Transition::BreakStart => "break_start",
+            Transition::BreakEnd => "break_end",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppState {
+    Working,
+    TakingABreak,
+}
+
+/// What happened to the timer, for the app to act on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The current period ran out
+    PeriodEnded,
+    /// The break just started is the long one at the end of the cycle
+    LongBreakDue,
+    /// The current period has a minute left
+    LastMinute,
+    /// The session has run until the time it was to end at
+    SessionOver,
+}
+
+/// Pomodoros and breaks following each other. It only changes when told to, and is told what
+/// time it is rather than looking, so the app decides what to do about the events it returns.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    pub state: AppState,
+    /// Pomodoros finished since the last long break
+    pub pomodoros_in_cycle: u32,
+    pomodoro_length: Duration,
+    break_length: Duration,
+    long_break_length: Duration,
+    pomodoros_per_cycle: u32,
+    /// When the session should end, with the periods fitted around it
+    pub until: Option<Instant>,
+    start_of_period: Instant,
+    /// Time added on to the current period
+    extra_time: Duration,
+    /// Since when the timer is paused
+    paused_since: Option<Instant>,
+    /// Time spent paused in the current period, not counting the ongoing pause
+    paused_time: Duration,
+    /// Whether the last minute of the current period was announced
+    last_minute_announced: bool,
+}
+
+impl Timer {
+    /// A pomodoro starting at `now`, with the lengths in `config`.
+    pub fn new(config: &Config, now: Instant) -> Timer {
+        Timer {
+            state: AppState::Working,
+            pomodoros_in_cycle: 0,
+            pomodoro_length: config.pomodoro_length,
+            break_length: config.break_length,
+            long_break_length: config.long_break_length,
+            pomodoros_per_cycle: config.pomodoros_per_cycle,
+            until: None,
+            start_of_period: now,
+            extra_time: Duration::ZERO,
+            paused_since: None,
+            paused_time: Duration::ZERO,
+            last_minute_announced: false,
+        }
+    }
+
+    /// Pick up in `state`, `elapsed` into it at `now`.
+    pub fn restore(
+        &mut self,
+        state: AppState,
+        pomodoros_in_cycle: u32,
+        elapsed: Duration,
+        now: Instant,
+    ) {
+        self.state = state;
+        self.pomodoros_in_cycle = pomodoros_in_cycle;
+        self.start_of_period = now.checked_sub(elapsed).unwrap_or(now);
+        self.extra_time = Duration::ZERO;
+        self.paused_since = None;
+        self.paused_time = Duration::ZERO;
+        self.last_minute_announced = false;
+    }
+
+    /// Time spent in the current period, not counting pauses.
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        let until = self.paused_since.unwrap_or(now);
+        until
+            .saturating_duration_since(self.start_of_period)
+            .saturating_sub(self.paused_time)
+    }
+
+    /// Length of the current period, with the time added on, fitted into the session.
+    pub fn period_length(&self, now: Instant) -> Duration {
+        let length = match self.state {
+            AppState::Working => self.pomodoro_length,
+            AppState::TakingABreak => self.break_length(),
+        }
+        .saturating_add(self.extra_time);
+        match self.until {
+            Some(until) => {
+                let time_left = until.saturating_duration_since(now) + self.elapsed(now);
+                let next_break = (self.state == AppState::Working).then(|| self.break_length());
+                fit_period(length, time_left, next_break)
+            }
+            None => length,
+        }
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.period_length(now).saturating_sub(self.elapsed(now))
+    }
+
+    /// Length of the current break, or the next one while working.
+    pub fn break_length(&self) -> Duration {
+        if self.is_long_break() {
+            self.long_break_length
+        } else {
+            self.break_length
+        }
+    }
+
+    /// Whether the current break, or the next one while working, is the long one at the end of
+    /// the cycle.
+    pub fn is_long_break(&self) -> bool {
+        match self.state {
+            AppState::Working => self.pomodoros_in_cycle + 1 >= self.pomodoros_per_cycle,
+            AppState::TakingABreak => self.pomodoros_in_cycle >= self.pomodoros_per_cycle,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// What's happened by `now`. The period doesn't end by itself: the app moves on to the next
+    /// one with `next_period` once it's dealt with the end of this one.
+    pub fn tick(&mut self, now: Instant) -> Vec<Event> {
+        if self.until.is_some_and(|until| now >= until) {
+            vec![Event::SessionOver]
+        } else if self.elapsed(now) > self.period_length(now) {
+            vec![Event::PeriodEnded]
+        } else if self.remaining(now) <= LAST_MINUTE && !self.last_minute_announced {
+            self.last_minute_announced = true;
+            vec![Event::LastMinute]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Switch between working and taking a break at `now`. A pause carries on into the next
+    /// period.
+    pub fn next_period(&mut self, now: Instant) -> Vec<Event> {
+        let mut events = Vec::new();
+        match self.state {
+            AppState::Working => {
+                self.pomodoros_in_cycle += 1;
+                self.state = AppState::TakingABreak;
+                if self.is_long_break() {
+                    events.push(Event::LongBreakDue);
+                }
+            }
+            AppState::TakingABreak => {
+                if self.is_long_break() {
+                    self.pomodoros_in_cycle = 0;
+                }
+                self.state = AppState::Working;
+            }
+        }
+        self.start_of_period = now;
+        self.extra_time = Duration::ZERO;
+        self.paused_since = self.paused_since.map(|_| now);
+        self.paused_time = Duration::ZERO;
+        self.last_minute_announced = false;
+        events
+    }
+
+    /// Make the current period longer.
+    pub fn add_time(&mut self, time: Duration) {
+        self.extra_time += time;
+    }
+
+    /// Stop the clock at `now`, returning whether it was running.
+    pub fn pause(&mut self, now: Instant) -> bool {
+        if self.paused_since.is_some() {
+            return false;
+        }
+        self.paused_since = Some(now);
+        true
+    }
+
+    /// Start the clock again at `now`, returning whether it was paused.
+    pub fn resume(&mut self, now: Instant) -> bool {
+        let Some(paused_since) = self.paused_since.take() else {
+            return false;
+        };
+        self.paused_time += now.saturating_duration_since(paused_since);
+        true
+    }
+}
+
+/// Shorten a period if it's longer than `time_left`. A pomodoro, which has a `next_break`, is
+/// stretched to `time_left` instead when there wouldn't be time for its break.
+pub fn fit_period(length: Duration, time_left: Duration, next_break: Option<Duration>) -> Duration {
+    let too_late_for_break =
+        next_break.is_some_and(|next_break| time_left.saturating_sub(length) < next_break);
+    if length >= time_left || too_late_for_break {
+        time_left
+    } else {
+        length
+    }
+}
+
+/// The next time the clock shows `time`, today or else tomorrow.
+pub fn next_time_of_day(time: NaiveTime) -> DateTime<Local> {
+    let now = Local::now();
+    let mut date = now.date_naive();
+    if time <= now.time() {
+        date = date.succ_opt().unwrap_or(date);
+    }
+    let target = date.and_time(time);
+    // A time skipped by a DST change is counted from now instead
+    target
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(|| now + (target - now.naive_local()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn timer(now: Instant) -> Timer {
+        let config = Config {
+            pomodoro_length: 25 * MINUTE,
+            break_length: 5 * MINUTE,
+            long_break_length: 15 * MINUTE,
+            pomodoros_per_cycle: 4,
+            ..Config::default()
+        };
+        Timer::new(&config, now)
+    }
+
+    #[test]
+    fn starts_with_a_pomodoro() {
+        let start = Instant::now();
+        let timer = timer(start);
+        assert_eq!(timer.state, AppState::Working);
+        assert_eq!(timer.pomodoros_in_cycle, 0);
+        assert!(!timer.is_paused());
+        assert_eq!(timer.elapsed(start), Duration::ZERO);
+        assert_eq!(timer.period_length(start), 25 * MINUTE);
+        assert_eq!(timer.remaining(start + 10 * MINUTE), 15 * MINUTE);
+    }
+
+    #[test]
+    fn period_ends_once_its_length_has_passed() {
+        let start = Instant::now();
+        let mut timer = timer(start);
+        assert_eq!(timer.tick(start + 24 * MINUTE), [Event::LastMinute]);
+        assert_eq!(timer.tick(start + 25 * MINUTE), []);
+        assert_eq!(
+            timer.tick(start + 25 * MINUTE + Duration::from_millis(1)),
+            [Event::PeriodEnded]
+        );
+        // Until the app moves on
+        assert_eq!(timer.state, AppState::Working);
+        assert_eq!(timer.tick(start + 26 * MINUTE), [Event::PeriodEnded]);
+    }
+
+    #[test]
+    fn last_minute_is_announced_once_per_period() {
+        let start = Instant::now();
+        let mut timer = timer(start);
+        assert_eq!(timer.tick(start + 23 * MINUTE), []);
+        assert_eq!(timer.tick(start + 24 * MINUTE), [Event::LastMinute]);
+        assert_eq!(
+            timer.tick(start + 24 * MINUTE + Duration::from_secs(30)),
+            []
+        );
+
+        let start = start + 25 * MINUTE;
+        timer.next_period(start);
+        assert_eq!(timer.tick(start + 4 * MINUTE), [Event::LastMinute]);
+    }
+
+    #[test]
+    fn pomodoros_and_breaks_alternate() {
+        let mut now = Instant::now();
+        let mut timer = timer(now);
+        now += 25 * MINUTE;
+        assert_eq!(timer.next_period(now), []);
+        assert_eq!(timer.state, AppState::TakingABreak);
+        assert_eq!(timer.pomodoros_in_cycle, 1);
+        assert_eq!(timer.elapsed(now), Duration::ZERO);
+        assert_eq!(timer.period_length(now), 5 * MINUTE);
+
+        now += 5 * MINUTE;
+        assert_eq!(timer.next_period(now), []);
+        assert_eq!(timer.state, AppState::Working);
+        assert_eq!(timer.pomodoros_in_cycle, 1);
+        assert_eq!(timer.period_length(now), 25 * MINUTE);
+    }
+
+    #[test]
+    fn long_break_ends_the_cycle() {
+        let mut now = Instant::now();
+        let mut timer = timer(now);
+        for _ in 0..3 {
+            assert!(!timer.is_long_break());
+            assert_eq!(timer.next_period(now), []);
+            assert_eq!(timer.next_period(now), []);
+        }
+        // The fourth pomodoro is followed by the long break
+        assert!(timer.is_long_break());
+        assert_eq!(timer.break_length(), 15 * MINUTE);
+        now += 25 * MINUTE;
+        assert_eq!(timer.next_period(now), [Event::LongBreakDue]);
+        assert_eq!(timer.pomodoros_in_cycle, 4);
+        assert_eq!(timer.period_length(now), 15 * MINUTE);
+
+        assert_eq!(timer.next_period(now + 15 * MINUTE), []);
+        assert_eq!(timer.pomodoros_in_cycle, 0);
+        assert!(!timer.is_long_break());
+        assert_eq!(timer.break_length(), 5 * MINUTE);
+    }
+
+    #[test]
+    fn pausing_stops_the_clock() {
+        let start = Instant::now();
+        let mut timer = timer(start);
+        assert!(timer.pause(start + MINUTE));
+        assert!(!timer.pause(start + 2 * MINUTE));
+        assert!(timer.is_paused());
+        assert_eq!(timer.elapsed(start + 10 * MINUTE), MINUTE);
+        assert_eq!(timer.tick(start + 30 * MINUTE), []);
+
+        assert!(timer.resume(start + 30 * MINUTE));
+        assert!(!timer.resume(start + 31 * MINUTE));
+        assert_eq!(timer.elapsed(start + 31 * MINUTE), 2 * MINUTE);
+        assert_eq!(timer.remaining(start + 31 * MINUTE), 23 * MINUTE);
+    }
+
+    #[test]
+    fn pause_carries_on_into_the_next_period() {
+        let start = Instant::now();
+        let mut timer = timer(start);
+        timer.pause(start + MINUTE);
+        timer.next_period(start + 2 * MINUTE);
+        assert!(timer.is_paused());
+        assert_eq!(timer.elapsed(start + 10 * MINUTE), Duration::ZERO);
+        timer.resume(start + 10 * MINUTE);
+        assert_eq!(timer.elapsed(start + 11 * MINUTE), MINUTE);
+    }
+
+    #[test]
+    fn added_time_lasts_for_the_period() {
+        let start = Instant::now();
+        let mut timer = timer(start);
+        timer.add_time(5 * MINUTE);
+        assert_eq!(timer.period_length(start), 30 * MINUTE);
+        assert_eq!(timer.tick(start + 26 * MINUTE), []);
+        assert_eq!(timer.tick(start + 31 * MINUTE), [Event::PeriodEnded]);
+        timer.next_period(start + 31 * MINUTE);
+        assert_eq!(timer.period_length(start + 31 * MINUTE), 5 * MINUTE);
+    }
+
+    #[test]
+    fn session_ends_at_until() {
+        let start = Instant::now();
+        let mut timer = timer(start);
+        timer.until = Some(start + 20 * MINUTE);
+        // Shortened to fit
+        assert_eq!(timer.period_length(start), 20 * MINUTE);
+        assert_eq!(timer.tick(start + 20 * MINUTE), [Event::SessionOver]);
+    }
+
+    #[test]
+    fn pomodoro_is_stretched_when_there_is_no_time_for_its_break() {
+        let start = Instant::now();
+        let mut timer = timer(start);
+        timer.until = Some(start + 28 * MINUTE);
+        assert_eq!(timer.period_length(start), 28 * MINUTE);
+        timer.until = Some(start + 30 * MINUTE);
+        assert_eq!(timer.period_length(start), 25 * MINUTE);
+    }
+
+    #[test]
+    fn restoring_picks_up_part_way_through() {
+        let now = Instant::now() + 60 * MINUTE;
+        let mut timer = timer(now);
+        timer.pause(now);
+        timer.restore(AppState::TakingABreak, 4, 3 * MINUTE, now);
+        assert!(!timer.is_paused());
+        assert!(timer.is_long_break());
+        assert_eq!(timer.elapsed(now), 3 * MINUTE);
+        assert_eq!(timer.remaining(now), 12 * MINUTE);
+    }
+
+    #[test]
+    fn fit_period_shortens_and_stretches() {
+        let break_length = Some(5 * MINUTE);
+        assert_eq!(
+            fit_period(25 * MINUTE, 60 * MINUTE, break_length),
+            25 * MINUTE
+        );
+        assert_eq!(
+            fit_period(25 * MINUTE, 10 * MINUTE, break_length),
+            10 * MINUTE
+        );
+        assert_eq!(
+            fit_period(25 * MINUTE, 29 * MINUTE, break_length),
+            29 * MINUTE
+        );
+        assert_eq!(fit_period(5 * MINUTE, 3 * MINUTE, None), 3 * MINUTE);
+        assert_eq!(fit_period(5 * MINUTE, 6 * MINUTE, None), 5 * MINUTE);
+    }
+}
@@ -0,0 +1,83 @@
+//! Taking tasks from [Todoist](https://todoist.com) through its REST API, and closing them there
+//! when they're done. The tasks fetched are kept in a file, to start with when Todoist can't be
+//! reached.
+
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::Path, thread, time::Duration};
+
+const API: &str = "https://api.todoist.com/rest/v2";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Todoist {
+    /// API token, from the Integrations settings
+    pub token: String,
+    /// Which tasks to take, as a Todoist filter
+    #[serde(default = "default_filter")]
+    pub filter: String,
+}
+
+fn default_filter() -> String {
+    "today".to_string()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Item {
+    pub id: String,
+    pub content: String,
+    /// 4 for p1, the most urgent, down to 1 for p4, the default. Not in caches from before it was
+    /// kept.
+    #[serde(default)]
+    pub priority: u8,
+}
+
+impl Item {
+    /// The task's priority, with 0 for p1. Tasks left at the default p4 have none.
+    pub fn priority(&self) -> Option<u8> {
+        (2..=4).contains(&self.priority).then(|| 4 - self.priority)
+    }
+}
+
+/// Where the tasks came from.
+pub enum Fetched {
+    Todoist(Vec<Item>),
+    /// Todoist couldn't be reached, these are the tasks from last time
+    Cache(Vec<Item>),
+}
+
+impl Todoist {
+    /// The tasks matching the filter, saved to `cache` for when Todoist can't be reached.
+    pub fn fetch(&self, cache: &Path) -> Result<Fetched, Box<dyn Error>> {
+        match self.tasks() {
+            Ok(items) => {
+                fs::write(cache, serde_json::to_string(&items)?)?;
+                Ok(Fetched::Todoist(items))
+            }
+            Err(e) => match fs::read_to_string(cache) {
+                Ok(items) => Ok(Fetched::Cache(serde_json::from_str(&items)?)),
+                Err(_) => Err(e),
+            },
+        }
+    }
+
+    fn tasks(&self) -> Result<Vec<Item>, Box<dyn Error>> {
+        Ok(ureq::get(&format!("{API}/tasks"))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .query("filter", &self.filter)
+            .timeout(TIMEOUT)
+            .call()?
+            .into_json()?)
+    }
+
+    /// Close the task, or reopen it, without waiting for the response.
+    pub fn set_done(&self, id: &str, done: bool) {
+        let action = if done { "close" } else { "reopen" };
+        let request = ureq::post(&format!("{API}/tasks/{id}/{action}"))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .timeout(TIMEOUT);
+        thread::spawn(move || {
+            // Nowhere to report a failure to, the task stays open in Todoist
+            let _ = request.call();
+        });
+    }
+}
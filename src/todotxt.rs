@@ -0,0 +1,71 @@
+//! Keeping the task list in sync with a [todo.txt](https://github.com/todotxt/todo.txt) file.
+//! Tasks are matched to lines by their text, without the completion mark and date.
+
+use chrono::Local;
+use std::{fs, io, path::Path};
+
+/// A line of the file.
+pub struct Item {
+    /// The line without the completion mark and date
+    pub text: String,
+    pub done: bool,
+}
+
+impl Item {
+    fn parse(line: &str) -> Item {
+        match line.strip_prefix("x ") {
+            Some(rest) => {
+                // The completion date is optional
+                let text = match rest.split_once(' ') {
+                    Some((date, text)) if is_date(date) => text,
+                    _ => rest,
+                };
+                Item {
+                    text: text.trim().to_string(),
+                    done: true,
+                }
+            }
+            None => Item {
+                text: line.trim().to_string(),
+                done: false,
+            },
+        }
+    }
+}
+
+fn is_date(word: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok()
+}
+
+pub fn read(path: &Path) -> io::Result<Vec<Item>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Item::parse)
+        .collect())
+}
+
+/// Mark the first line matching `is_task` as done, with today's date, or as not done.
+pub fn set_done(path: &Path, is_task: impl Fn(&str) -> bool, done: bool) -> io::Result<()> {
+    let file = fs::read_to_string(path)?;
+    let mut found = false;
+    let lines: Vec<String> = file
+        .lines()
+        .map(|line| {
+            let item = Item::parse(line);
+            if found || line.trim().is_empty() || !is_task(&item.text) {
+                return line.to_string();
+            }
+            found = true;
+            if done {
+                format!("x {} {}", Local::now().format("%Y-%m-%d"), item.text)
+            } else {
+                item.text
+            }
+        })
+        .collect();
+    if found {
+        fs::write(path, lines.join("\n") + "\n")?;
+    }
+    Ok(())
+}
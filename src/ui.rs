@@ -0,0 +1,1367 @@
+//! Drawing the timer in the terminal, and the keys and clicks it takes.
+
+use chrono::{DateTime, Timelike, Utc};
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, List, ListItem, Padding, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Tabs,
+    },
+    Frame, Terminal,
+};
+use std::{
+    io::{self, Stdout},
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+    time::Duration,
+};
+
+use crate::{
+    app::App,
+    bigtext,
+    config::PaneLayout,
+    event::{self, Event},
+    format,
+    gauge::GlyphGauge,
+    history::{self, PeriodKind},
+    keymap::{self, Action, Mode},
+    task::{SortOrder, Task},
+    theme::Theme,
+    timer::AppState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Timer,
+    Tasks,
+    Stats,
+    History,
+}
+
+impl Tab {
+    const ALL: [Tab; 4] = [Tab::Timer, Tab::Tasks, Tab::Stats, Tab::History];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Tab::Timer => "Timer",
+            Tab::Tasks => "Tasks",
+            Tab::Stats => "Stats",
+            Tab::History => "History",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        Tab::ALL
+            .iter()
+            .position(|tab| tab == self)
+            .unwrap_or_default()
+    }
+
+    pub fn next(&self) -> Tab {
+        Tab::ALL[(self.index() + 1) % Tab::ALL.len()]
+    }
+}
+
+/// Whether the terminal is taken over, for the panic hook to know whether to give it back.
+static TAKEN_OVER: AtomicBool = AtomicBool::new(false);
+
+/// The terminal, taken over for the TUI until this is dropped, including by a panic unwinding.
+/// A panic that doesn't unwind still gets the terminal back before its message is printed.
+pub struct TerminalGuard {
+    pub terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    pub fn enter() -> io::Result<TerminalGuard> {
+        static PANIC_HOOK: Once = Once::new();
+        PANIC_HOOK.call_once(|| {
+            let print_panic = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                give_back_terminal();
+                print_panic(info);
+            }));
+        });
+
+        enable_raw_mode()?;
+        TAKEN_OVER.store(true, Ordering::SeqCst);
+        let terminal = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+            .and_then(|()| Terminal::new(CrosstermBackend::new(io::stdout())));
+        match terminal {
+            Ok(terminal) => Ok(TerminalGuard { terminal }),
+            Err(e) => {
+                give_back_terminal();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        give_back_terminal();
+    }
+}
+
+/// Leave raw mode and the alternate screen, and stop capturing the mouse, if the terminal is
+/// taken over. There's nothing to do about failing to, so that's ignored.
+fn give_back_terminal() {
+    if TAKEN_OVER.swap(false, Ordering::SeqCst) {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            SetTitle(""),
+            Show
+        );
+    }
+}
+
+/// What's on screen that changes by itself as time passes, to only draw again when it has.
+#[derive(PartialEq)]
+struct Shown {
+    state: AppState,
+    paused: bool,
+    remaining_secs: u64,
+    elapsed_secs: u64,
+    tracked_secs: Option<i64>,
+    minute: u32,
+    animating: bool,
+}
+
+impl Shown {
+    fn of(app: &App) -> Shown {
+        let now = app.clock.now();
+        Shown {
+            state: app.timer.state,
+            paused: app.timer.is_paused(),
+            remaining_secs: app.remaining().as_secs(),
+            elapsed_secs: app.elapsed().as_secs(),
+            tracked_secs: (app.tasks.get_selected())
+                .map(|task| task.tracked_duration(now).num_seconds()),
+            minute: app.clock.local(now).minute(),
+            animating: app.is_animating(),
+        }
+    }
+}
+
+/// Draw `app` and handle events until it's quit or stopped.
+pub fn run<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    event::terminal(app.queue.0.clone());
+    let mut title = String::new();
+    let mut shown = None;
+    let mut changed = true;
+    loop {
+        let now_shown = Shown::of(app);
+        if changed || app.is_animating() || shown.as_ref() != Some(&now_shown) {
+            terminal.draw(|f| ui(f, app))?;
+            shown = Some(now_shown);
+        }
+
+        // Only when it changes, as some multiplexers redraw on every title update
+        let new_title = app.terminal_title();
+        if new_title != title {
+            execute!(io::stdout(), SetTitle(&new_title))?;
+            title = new_title;
+        }
+
+        let Some(event) = app.next_event(app.tick_timeout()) else {
+            return Ok(());
+        };
+        // A tick only changes what's on screen through what's in `Shown`
+        changed = !matches!(event, Event::Tick);
+        app.handle_event(event);
+        // Catch up on whatever else happened before drawing again
+        while let Ok(event) = app.queue.1.try_recv() {
+            changed |= !matches!(event, Event::Tick);
+            app.handle_event(event);
+        }
+        if app.stopped {
+            return Ok(());
+        }
+    }
+}
+
+fn task_list_item<'a>(
+    task: &'a Task,
+    icon: &str,
+    theme: &Theme,
+    now: DateTime<Utc>,
+) -> ListItem<'a> {
+    let color = if task.is_complete {
+        theme.complete_task
+    } else if task.over_estimate {
+        theme.over_estimate
+    } else {
+        theme.incomplete_task
+    };
+    let estimate = match task.estimate {
+        Some(estimate) if task.over_estimate => format!(" (over ~{estimate})"),
+        Some(estimate) => format!(" (~{estimate})"),
+        None => String::new(),
+    };
+    let mut style = Style::default().fg(color);
+    if theme.symbols {
+        if task.is_complete {
+            style = style.add_modifier(Modifier::DIM);
+        } else if task.over_estimate {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+    }
+    let icon = if icon.is_empty() {
+        String::new()
+    } else {
+        format!("{icon} ")
+    };
+    ListItem::new(format!(
+        "{}{}{} : {}: {}",
+        icon,
+        task.name,
+        estimate,
+        format::chrono_duration(task.tracked_duration(now)),
+        task.work_periods.len() + usize::from(task.is_active())
+    ))
+    .style(style)
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    app.task_list_area = None;
+    if app.compact || f.size().height < COMPACT_HEIGHT {
+        return compact_ui(f, app);
+    }
+    if app.is_break_takeover() {
+        return break_takeover_ui(f, app);
+    }
+    if app.is_flashing() {
+        f.render_widget(
+            Block::default().style(Style::default().bg(app.state_color())),
+            f.size(),
+        );
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let titles: Vec<Line> = Tab::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| Line::from(format!("{} {}", i + 1, tab.title())))
+        .collect();
+    // The header goes to the right of the tabs, when there's room for it
+    let tabs_width = titles.iter().map(|title| title.width() + 3).sum::<usize>();
+    let header = header(app);
+    let header_width = if tabs_width + header.width() <= usize::from(chunks[0].width) {
+        header.width() as u16
+    } else {
+        0
+    };
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(header_width)].as_ref())
+        .split(chunks[0]);
+    f.render_widget(Paragraph::new(header).alignment(Alignment::Right), top[1]);
+    let tabs = Tabs::new(titles)
+        .select(app.tab.index())
+        .style(Style::default().fg(app.theme.muted))
+        .highlight_style(
+            Style::default()
+                .fg(app.state_color())
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(tabs, top[0]);
+
+    match app.tab {
+        Tab::Timer => pomodoro_ui(f, app, chunks[1]),
+        Tab::Tasks => planner_ui(f, app, chunks[1]),
+        Tab::Stats => stats_ui(f, app, chunks[1]),
+        Tab::History => history_ui(f, app, chunks[1]),
+    }
+
+    status_bar(f, app, chunks[2]);
+
+    if app.show_help {
+        help_ui(f, &app.theme);
+    }
+    if app.confirm_quit {
+        quit_ui(f, app);
+    }
+    task_input_ui(f, app);
+    toast_ui(f, app, chunks[1]);
+}
+
+/// The toast in the bottom right corner of `area`.
+fn toast_ui<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let Some((message, fading)) = app.current_toast() else {
+        return;
+    };
+    let mut style = Style::default().fg(app.theme.accent);
+    if fading {
+        style = style.add_modifier(Modifier::DIM);
+    }
+
+    let width = (message.chars().count() as u16 + 4).min(area.width);
+    let height = 3.min(area.height);
+    let area = Rect::new(area.right() - width, area.bottom() - height, width, height);
+    let toast = Paragraph::new(Span::styled(message, style)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(style)
+            .padding(Padding::horizontal(1)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(toast, area);
+}
+
+/// The whole screen during breaks, with a huge countdown, until a key is pressed.
+fn break_takeover_ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let style = Style::default()
+        .fg(app.theme.taking_a_break)
+        .add_modifier(Modifier::DIM);
+    let area = f.size();
+    let remaining = app.remaining().as_secs();
+    let clock = format!("{:02}:{:02}", remaining / 60, remaining % 60);
+
+    let mut lines = Vec::new();
+    match bigtext::render(&clock) {
+        Some(big_clock) if usize::from(area.width) >= bigtext::width(&clock) => {
+            // Twice as tall when there's room
+            let scale = if usize::from(area.height) >= bigtext::HEIGHT * 2 + 4 {
+                2
+            } else {
+                1
+            };
+            for line in big_clock {
+                for _ in 0..scale {
+                    lines.push(Line::from(Span::styled(line.clone(), style)));
+                }
+            }
+        }
+        _ => lines.push(Line::from(Span::styled(clock, style))),
+    }
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        "Stand up, stretch and look away from the screen",
+        style
+            .remove_modifier(Modifier::DIM)
+            .add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(Span::styled(
+        "Press any key to go back to the timer",
+        Style::default().fg(app.theme.muted),
+    )));
+
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let area = Rect::new(area.x, area.y + top, area.width, area.height - top);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+}
+
+/// The date and time, and since when this session has been running.
+fn header(app: &App) -> Line<'static> {
+    let muted = Style::default().fg(app.theme.muted);
+    let now = app.clock.local(app.clock.now());
+    Line::from(vec![
+        Span::raw(now.format("%a %e %b %H:%M").to_string()),
+        Span::styled(" │ ", muted),
+        Span::styled(
+            format!(
+                "session since {}",
+                app.clock.local(app.session_started_at).format("%H:%M")
+            ),
+            muted,
+        ),
+    ])
+}
+
+/// Below this many rows the panes don't fit, so the single line view is used.
+const COMPACT_HEIGHT: u16 = 15;
+pub const COMPACT_GAUGE_WIDTH: usize = 10;
+
+/// `[████      ] 12:34 Task name`, for tiny terminals.
+fn compact_ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let progress = (app.elapsed().as_secs_f64() / app.period_length().as_secs_f64()).min(1.0);
+    let remaining = app.remaining().as_secs();
+
+    let timer = Span::styled(
+        format!(
+            "[{}] {:02}:{:02} ",
+            app.config.gauge_glyphs.bar(progress, COMPACT_GAUGE_WIDTH),
+            remaining / 60,
+            remaining % 60
+        ),
+        Style::default().fg(app.state_color()),
+    );
+    let area = f.size();
+    let accent = Style::default().fg(app.theme.accent);
+    // Questions and the new task take the place of the task, as there's no room for dialogs
+    let rest = if app.confirm_quit {
+        Span::styled(quit_question(app), accent)
+    } else if let Some(name) = &app.new_task {
+        let prompt = "New task: ";
+        // Room for the cursor after the text
+        let room =
+            usize::from(area.width).saturating_sub(timer.width() + prompt.chars().count() + 1);
+        let shown = format!("{prompt}{}", last_chars(name, room));
+        let cursor_x = timer.width() + shown.chars().count();
+        f.set_cursor(area.x + cursor_x as u16, area.y);
+        Span::styled(shown, accent)
+    } else if app.tasks.items.is_empty() {
+        Span::styled(no_tasks_hint(), Style::default().fg(app.theme.muted))
+    } else {
+        Span::raw(app.get_current_task_name().cloned().unwrap_or_default())
+    };
+    f.render_widget(Paragraph::new(Line::from(vec![timer, rest])), area);
+}
+
+fn status_bar<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let separator = Span::styled(" │ ", Style::default().fg(app.theme.muted));
+    let task = app
+        .get_current_task_name()
+        .map_or("no task".to_string(), |name| format!("▶ {name}"));
+    let cycle_position = match app.timer.state {
+        AppState::Working => app.timer.pomodoros_in_cycle + 1,
+        AppState::TakingABreak => app.timer.pomodoros_in_cycle,
+    };
+    let sound = if app.muted {
+        "🔇 muted"
+    } else if app.audio.is_none() {
+        NO_AUDIO_INDICATOR
+    } else {
+        "🔊 sound on"
+    };
+
+    let status = Line::from(vec![
+        Span::styled(task, Style::default().fg(app.state_color())),
+        separator.clone(),
+        Span::raw(format!(
+            "{} today",
+            app.history.pomodoros_today(app.clock.as_ref()).count()
+        )),
+        separator.clone(),
+        Span::raw(format!(
+            "{cycle_position}/{} until long break",
+            app.config.pomodoros_per_cycle
+        )),
+        separator,
+        Span::raw(sound),
+    ]);
+    // As many key hints as fit beside the status
+    let room = usize::from(area.width).saturating_sub(status.width());
+    let mut hints = Vec::new();
+    let mut width = 0;
+    for (key, hint) in keymap::hints(app.mode()) {
+        let hint_width = key.chars().count() + hint.chars().count() + 3;
+        if width + hint_width > room {
+            break;
+        }
+        width += hint_width;
+        hints.push(Span::raw("  "));
+        hints.push(Span::styled(
+            key,
+            Style::default()
+                .fg(app.theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+        hints.push(Span::styled(
+            format!(" {hint}"),
+            Style::default().fg(app.theme.muted),
+        ));
+    }
+    f.render_widget(
+        Paragraph::new(Line::from(hints)).alignment(Alignment::Right),
+        area,
+    );
+    f.render_widget(Paragraph::new(status), area);
+}
+
+/// What every key does while the help is open, with the keys for the same action on one line
+/// and closing the help first.
+fn help_ui<B: Backend>(f: &mut Frame<B>, theme: &Theme) {
+    let color = theme.accent;
+
+    let mut actions: Vec<(Action, Vec<String>)> = Vec::new();
+    let mut seen = Vec::new();
+    for (key, action) in keymap::bindings(Mode::Help) {
+        // Keys further down are shadowed, like `Esc` closing the help instead of quitting
+        if seen.contains(key) {
+            continue;
+        }
+        seen.push(*key);
+        match actions.iter_mut().find(|(existing, _)| existing == action) {
+            Some((_, keys)) => keys.push(keymap::key_name(*key)),
+            None => actions.push((*action, vec![keymap::key_name(*key)])),
+        }
+    }
+    let keys: Vec<String> = actions.iter().map(|(_, keys)| keys.join(", ")).collect();
+    let key_width = keys
+        .iter()
+        .map(|keys| keys.chars().count())
+        .max()
+        .unwrap_or_default();
+    let lines: Vec<Line> = keys
+        .iter()
+        .zip(&actions)
+        .map(|(keys, (action, _))| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{keys:>key_width$}  "),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(action.description()),
+            ])
+        })
+        .collect();
+
+    let width = lines.iter().map(Line::width).max().unwrap_or_default() + 2;
+    let size = f.size();
+    let width = (width as u16).min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let help = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Help ─ Esc to close ")
+            .border_style(Style::default().fg(color)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
+
+fn quit_question(app: &App) -> String {
+    let minutes = app.elapsed().as_secs() / 60;
+    format!(
+        "Quit and discard {minutes} minute{}? y/n",
+        if minutes == 1 { "" } else { "s" }
+    )
+}
+
+fn quit_ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let color = app.theme.accent;
+    let question = quit_question(app);
+
+    let area = f.size();
+    let width = (question.chars().count() as u16 + 4).min(area.width);
+    let height = 3.min(area.height);
+    let area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let dialog = Paragraph::new(question).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(dialog, area);
+}
+
+/// Where a new task is typed in, with its end in view.
+fn task_input_ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let Some(name) = &app.new_task else {
+        return;
+    };
+    let area = f.size();
+    let width = TASK_INPUT_WIDTH.min(area.width);
+    let height = 3.min(area.height);
+    let area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    // Room for the cursor after the text, inside the borders
+    let shown = last_chars(name, usize::from(width.saturating_sub(3)));
+    let cursor_x = area.x + 1 + shown.chars().count() as u16;
+    let input = Paragraph::new(shown).block(
+        Block::default()
+            .title(" New task ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.accent)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(input, area);
+    f.set_cursor(cursor_x, area.y + 1);
+}
+
+const TASK_INPUT_WIDTH: u16 = 50;
+
+/// The end of `text`, up to `room` characters of it.
+fn last_chars(text: &str, room: usize) -> String {
+    text.chars()
+        .skip(text.chars().count().saturating_sub(room))
+        .collect()
+}
+
+/// What to show instead of an empty task list.
+fn no_tasks_hint() -> String {
+    match keymap::bindings(Mode::Tasks).find(|(_, action)| *action == Action::AddTask) {
+        Some((key, _)) => format!("No tasks yet, press {} to add one", keymap::key_name(*key)),
+        None => "No tasks yet".to_string(),
+    }
+}
+
+const NO_AUDIO_INDICATOR: &str = "🔇 no audio";
+
+/// Whether `area` has room to put panes side by side. Terminal cells are about twice as tall
+/// as they are wide, so this is when it's more than 1.5 times wider than tall on screen.
+fn is_wide(area: Rect) -> bool {
+    area.width >= 120 && area.width >= area.height * 3
+}
+
+/// A dot per pomodoro in the cycle, filled in once done, followed by the long break.
+fn cycle_dots(app: &App) -> Line<'static> {
+    let done = Style::default().fg(app.state_color());
+    let to_do = Style::default().fg(app.theme.muted);
+
+    let mut dots: Vec<Span> = (0..app.config.pomodoros_per_cycle)
+        .map(|i| {
+            if i < app.timer.pomodoros_in_cycle {
+                Span::styled("● ", done)
+            } else {
+                Span::styled("○ ", to_do)
+            }
+        })
+        .collect();
+    let long_break_style = if app.is_long_break() { done } else { to_do };
+    dots.push(Span::styled("☕ long break", long_break_style));
+    Line::from(dots)
+}
+
+fn pomodoro_ui<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let task_pane = match app.config.layout {
+        PaneLayout::Both => Constraint::Ratio(1, 3),
+        PaneLayout::TimerOnly => Constraint::Length(0),
+        // Task management mode
+        PaneLayout::TasksOnly => return planner_ui(f, app, area),
+    };
+    // The gauge, the time and the task list. On wide terminals the task list goes beside the
+    // other two, instead of underneath them.
+    let chunks = if app.config.layout == PaneLayout::Both && is_wide(area) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .margin(1)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(area);
+        let timer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Ratio(1, 3), Constraint::Min(0)].as_ref())
+            .split(columns[0]);
+        vec![timer[0], timer[1], columns[1]]
+    } else {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Ratio(1, 3), Constraint::Min(0), task_pane].as_ref())
+            .split(area)
+            .to_vec()
+    };
+
+    let remaining_min = app.remaining().as_secs() / 60;
+    let remaining_secs = app.remaining().as_secs() % 60;
+
+    let color = app.state_color();
+    let action = match app.timer.state {
+        AppState::Working => "Task",
+        AppState::TakingABreak => "Break",
+    };
+
+    let gauge_title = match app.timer.state {
+        _ if app.timer.is_paused() => " Pomodoro ─ ⏸ paused ",
+        AppState::Working if app.theme.symbols => " Pomodoro ─ ▶ working ",
+        AppState::TakingABreak if app.theme.symbols => " Pomodoro ─ ☕ break ",
+        _ => " Pomodoro ",
+    };
+    let progress = (app.elapsed().as_secs_f64() / app.period_length().as_secs_f64()).min(1.0);
+    let mut gauge = GlyphGauge::new(app.config.gauge_glyphs)
+        .block(
+            Block::default()
+                .title(Span::styled(gauge_title, Style::default().fg(color)))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(color)),
+        )
+        .style(if app.is_flashing() {
+            Style::default().fg(color).add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(color)
+        })
+        .ratio(progress);
+    if app.config.gauge_percentage {
+        gauge = gauge.label(format!("{}%", (progress * 100.0) as u16));
+    }
+    // The task list's progress goes underneath when there's room for both gauges
+    if chunks[0].height >= 6 {
+        let gauges = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+            .split(chunks[0]);
+        f.render_widget(gauge, gauges[0]);
+        f.render_widget(task_list_gauge(app), gauges[1]);
+    } else {
+        f.render_widget(gauge, chunks[0]);
+    }
+
+    let time_remaining_text = if !app.remaining().is_zero() {
+        format::duration(app.remaining())
+    } else {
+        format!("{action} completed")
+    };
+
+    // On breaks the breathing animation keeps rows of its own, under the clock
+    let (clock_area, breathing_area) = if app.timer.state == AppState::TakingABreak {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(BREATHING_HEIGHT)].as_ref())
+            .split(chunks[1]);
+        (rows[0], Some(rows[1]))
+    } else {
+        (chunks[1], None)
+    };
+
+    let mut lines = vec![cycle_dots(app)];
+    // Big digits when they fit under the dots, the plain text otherwise
+    let clock = format!("{remaining_min:02}:{remaining_secs:02}");
+    let big_clock_fits = usize::from(clock_area.width) >= bigtext::width(&clock)
+        && usize::from(clock_area.height) > bigtext::HEIGHT;
+    match bigtext::render(&clock) {
+        Some(big_clock) if big_clock_fits && !app.remaining().is_zero() => {
+            lines.extend(
+                big_clock
+                    .into_iter()
+                    .map(|line| Line::from(Span::styled(line, Style::default().fg(color)))),
+            );
+        }
+        _ => lines.push(Line::from(Span::styled(
+            time_remaining_text,
+            Style::default().fg(color),
+        ))),
+    }
+    if let (AppState::Working, Some(quote)) = (&app.timer.state, app.quote) {
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled(
+            app.quotes[quote].clone(),
+            Style::default().add_modifier(Modifier::ITALIC),
+        )));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default())
+        .alignment(Alignment::Center)
+        .block(Block::default());
+
+    f.render_widget(paragraph, clock_area);
+    if let Some(area) = breathing_area {
+        f.render_widget(
+            Paragraph::new(breathing(app.elapsed(), color).to_vec()).alignment(Alignment::Center),
+            area,
+        );
+    }
+
+    if app.config.layout == PaneLayout::Both {
+        task_list(f, app, chunks[2], color);
+    }
+}
+
+/// How long a breath, in and out, takes in the break animation.
+const BREATH_LENGTH: Duration = Duration::from_secs(8);
+const BREATH_WIDTH: usize = 13;
+/// Rows the break animation takes up.
+const BREATHING_HEIGHT: u16 = 2;
+
+/// A row of dots growing and shrinking with the breath `elapsed` into the break, over what to do.
+fn breathing(elapsed: Duration, color: Color) -> [Line<'static>; 2] {
+    let phase = elapsed.as_millis() % BREATH_LENGTH.as_millis();
+    let half = BREATH_LENGTH.as_millis() / 2;
+    let (fullness, prompt) = if phase < half {
+        (phase, "breathe in")
+    } else {
+        (BREATH_LENGTH.as_millis() - phase, "breathe out")
+    };
+    // Always an odd number of dots, so they stay centred
+    let dots = 1 + 2 * (fullness * (BREATH_WIDTH as u128 / 2) / half) as usize;
+    let style = Style::default().fg(color);
+    [
+        Line::from(Span::styled(vec!["●"; dots].join(" "), style)),
+        Line::from(Span::styled(prompt, style.add_modifier(Modifier::DIM))),
+    ]
+}
+
+/// Progress through the whole task list: tracked against estimated pomodoros when tasks have
+/// estimates, completed tasks otherwise.
+fn task_list_gauge(app: &App) -> GlyphGauge<'static> {
+    let tasks = &app.tasks.items;
+    let estimated: u32 = tasks.iter().filter_map(|task| task.estimate).sum();
+    let (ratio, label) = if estimated > 0 {
+        let tracked = tasks
+            .iter()
+            .filter(|task| task.estimate.is_some())
+            .map(|task| {
+                task.tracked_duration(app.clock.now())
+                    .to_std()
+                    .unwrap_or_default()
+            })
+            .sum::<Duration>()
+            .as_secs_f64()
+            / app.config.pomodoro_length.as_secs_f64();
+        (
+            tracked / f64::from(estimated),
+            format!("{tracked:.1}/{estimated} pomodoros"),
+        )
+    } else {
+        let completed = tasks.iter().filter(|task| task.is_complete).count();
+        (
+            completed as f64 / tasks.len().max(1) as f64,
+            format!("{completed}/{} tasks", tasks.len()),
+        )
+    };
+
+    let color = app.theme.accent;
+    GlyphGauge::new(app.config.gauge_glyphs)
+        .block(
+            Block::default()
+                .title(Span::styled(" Progress ", Style::default().fg(color)))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(color)),
+        )
+        .style(Style::default().fg(color))
+        .ratio(ratio)
+        .label(label)
+}
+
+fn planner_ui<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let color = app.theme.accent;
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(area);
+
+    task_list(f, app, chunks[0], color);
+    task_detail_ui(f, app, chunks[1]);
+}
+
+/// The work periods of the selected task, with the pomodoro of the day each one started in.
+fn task_detail_ui<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let color = app.theme.accent;
+    let Some(task) = app.tasks.get_selected() else {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Work Periods ")
+            .border_style(Style::default().fg(color));
+        f.render_widget(block, area);
+        return;
+    };
+
+    // The period still going on has no end yet
+    let periods = (task.work_periods.iter())
+        .map(|period| (period.start, Some(period.end)))
+        .chain(task.active_since.map(|start| (start, None)));
+    let items: Vec<ListItem> = periods
+        .map(|(start, end)| {
+            let open = end.is_none();
+            let end = end.unwrap_or_else(|| app.clock.now());
+            let local_start = app.clock.local(start);
+            let pomodoro = app
+                .history
+                .pomodoros()
+                .filter(|record| {
+                    record.end <= start
+                        && app.clock.local(record.start).date_naive() == local_start.date_naive()
+                })
+                .count()
+                + 1;
+            let until = if open {
+                "now  ".to_string()
+            } else {
+                app.clock.local(end).format("%H:%M").to_string()
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!(
+                    "{} {}–{until} ",
+                    local_start.format("%Y-%m-%d"),
+                    local_start.format("%H:%M"),
+                )),
+                Span::styled(
+                    format!("#{pomodoro} "),
+                    Style::default().fg(app.theme.working),
+                ),
+                Span::raw(format::chrono_duration(end - start)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", task.name))
+            .border_style(Style::default().fg(color)),
+    );
+    f.render_widget(list, area);
+}
+
+/// The task list, with its position and a scrollbar when it doesn't fit.
+fn task_list<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, color: Color) {
+    let icons = &app.config.task_icons;
+    let selected = app.tasks.state.selected();
+    let mut items: Vec<ListItem> = app
+        .tasks
+        .order
+        .iter()
+        .enumerate()
+        .map(|(position, &i)| {
+            let task = &app.tasks.items[i];
+            let icon = if selected == Some(position) {
+                match app.timer.state {
+                    AppState::Working => &icons.active,
+                    AppState::TakingABreak => &icons.paused,
+                }
+            } else if task.is_complete {
+                &icons.done
+            } else {
+                &icons.todo
+            };
+            task_list_item(task, icon, &app.theme, app.clock.now())
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(ListItem::new(Span::styled(
+            no_tasks_hint(),
+            Style::default().fg(app.theme.muted),
+        )));
+    }
+
+    let count = app.tasks.items.len();
+    let title = match app.tasks.sort {
+        SortOrder::Manual => " Task List ".to_string(),
+        sort => format!(" Task List ─ by {} ", sort.name()),
+    };
+    let position = match app.tasks.state.selected() {
+        Some(i) => format!(" {}/{count} ", i + 1),
+        None => format!(" {count} "),
+    };
+
+    let items = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title(
+                    Title::from(position)
+                        .alignment(Alignment::Right)
+                        .position(Position::Bottom),
+                )
+                .border_style(Style::default().fg(color)),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    // We can now render the item list
+    f.render_stateful_widget(items, area, &mut app.tasks.state);
+    app.task_list_area = Some(area);
+
+    let visible_rows = usize::from(area.height.saturating_sub(2));
+    if count > visible_rows {
+        let mut scrollbar_state = ScrollbarState::default()
+            .content_length(count as u16)
+            .viewport_content_length(visible_rows as u16)
+            .position(app.tasks.state.selected().unwrap_or_default() as u16);
+        let scrollbar = Scrollbar::default()
+            .orientation(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .style(Style::default().fg(color));
+        f.render_stateful_widget(
+            scrollbar,
+            area.inner(&Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+fn stats_ui<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let color = app.theme.accent;
+    let total = |records: &mut dyn Iterator<Item = &history::Record>| {
+        records.fold(chrono::Duration::zero(), |acc, record| {
+            acc + record.duration()
+        })
+    };
+
+    let heading = |text: &'static str| {
+        Line::from(Span::styled(
+            text,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))
+    };
+
+    let mut lines = vec![
+        heading("Today"),
+        Line::from(format!(
+            "Pomodoros: {}",
+            app.history.pomodoros_today(app.clock.as_ref()).count()
+        )),
+        Line::from(format!(
+            "Focus time: {}",
+            format::chrono_duration(total(&mut app.history.pomodoros_today(app.clock.as_ref())))
+        )),
+    ];
+    lines.extend(timeline(app, area.width.saturating_sub(2)));
+    lines.extend([
+        Line::from(""),
+        heading("All time"),
+        Line::from(format!("Pomodoros: {}", app.history.pomodoros().count())),
+        Line::from(format!(
+            "Focus time: {}",
+            format::chrono_duration(total(&mut app.history.pomodoros()))
+        )),
+        Line::from(""),
+        heading("Tasks this session"),
+    ]);
+    lines.extend(app.tasks.items.iter().map(|task| {
+        Line::from(format!(
+            "{}: {}",
+            task.name,
+            format::chrono_duration(task.tracked_duration(app.clock.now()))
+        ))
+    }));
+
+    let stats = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Stats ")
+            .border_style(Style::default().fg(color)),
+    );
+    f.render_widget(stats, area);
+}
+
+/// Today from the first period until now, `width` columns wide: work and breaks in their colors,
+/// gaps in grey. The times it goes from and to are underneath.
+fn timeline(app: &App, width: u16) -> Vec<Line<'static>> {
+    let now = app.clock.now();
+    let current = history::Record {
+        kind: match app.timer.state {
+            AppState::Working => PeriodKind::Work,
+            AppState::TakingABreak => PeriodKind::Break,
+        },
+        start: app.period_started_at,
+        end: now,
+        task: None,
+        git: None,
+    };
+    let records: Vec<&history::Record> = app
+        .history
+        .today(app.clock.as_ref())
+        .chain(std::iter::once(&current))
+        .collect();
+    let start = records
+        .iter()
+        .map(|record| record.start)
+        .min()
+        .unwrap_or(now);
+    let length = (now - start).num_seconds().max(1);
+    let width = i64::from(width.max(1));
+
+    let spans: Vec<Span> = (0..width)
+        .map(|x| {
+            let time = start + chrono::Duration::seconds(length * x / width);
+            let kind = records
+                .iter()
+                .find(|record| record.start <= time && time < record.end)
+                .map(|record| record.kind);
+            match kind {
+                Some(PeriodKind::Work) => Span::styled("█", Style::default().fg(app.theme.working)),
+                Some(PeriodKind::Break) => {
+                    Span::styled("█", Style::default().fg(app.theme.taking_a_break))
+                }
+                Some(PeriodKind::Interrupted) => {
+                    Span::styled("▄", Style::default().fg(app.theme.working))
+                }
+                None => Span::styled("─", Style::default().fg(app.theme.muted)),
+            }
+        })
+        .collect();
+
+    let from = app.clock.local(start).format("%H:%M").to_string();
+    let to = app.clock.local(now).format("%H:%M").to_string();
+    let padding = (width as usize).saturating_sub(from.len() + to.len());
+    vec![
+        Line::from(spans),
+        Line::from(Span::styled(
+            format!("{from}{}{to}", " ".repeat(padding)),
+            Style::default().fg(app.theme.muted),
+        )),
+    ]
+}
+
+fn history_ui<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let color = app.theme.accent;
+    let items: Vec<ListItem> = app
+        .history
+        .records
+        .iter()
+        .rev()
+        .map(|record| {
+            let (kind, color) = match record.kind {
+                PeriodKind::Work => ("work ", app.theme.working),
+                PeriodKind::Break => ("break", app.theme.taking_a_break),
+                PeriodKind::Interrupted => ("quit ", app.theme.muted),
+            };
+            let start = app.clock.local(record.start);
+            let end = app.clock.local(record.end);
+            Line::from(vec![
+                Span::raw(format!(
+                    "{} {}–{} ",
+                    start.format("%Y-%m-%d"),
+                    start.format("%H:%M"),
+                    end.format("%H:%M")
+                )),
+                Span::styled(kind, Style::default().fg(color)),
+                Span::raw(format!(" {}", record.task.as_deref().unwrap_or_default())),
+            ])
+        })
+        .map(ListItem::new)
+        .collect();
+
+    let history = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" History ")
+            .border_style(Style::default().fg(color)),
+    );
+    f.render_widget(history, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::TICK_RATE,
+        clock::{Clock, ManualClock},
+        config::Config,
+        history::History,
+    };
+    use crossterm::event::KeyCode;
+    use ratatui::backend::TestBackend;
+    use std::{env, fs, path::PathBuf, time::Duration};
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn history_path(test: &str) -> PathBuf {
+        env::temp_dir().join(format!("pomors-ui-{test}-{}.json", std::process::id()))
+    }
+
+    /// An app 10 minutes into working on the first of `tasks`, the last of them done.
+    fn app(tasks: &[&str], test: &str) -> (App, ManualClock) {
+        let clock = ManualClock::new(DateTime::from_timestamp(1_714_640_400, 0).unwrap());
+        let config = Config {
+            pomodoro_length: 25 * MINUTE,
+            break_length: 5 * MINUTE,
+            sound: false,
+            notifications: false,
+            ..Config::default()
+        };
+        let history = History::load(&history_path(test)).unwrap();
+        let mut app = App::new(
+            tasks.iter().map(|task| task.to_string()).collect(),
+            config,
+            None,
+            history,
+            Box::new(clock.clone()),
+        );
+        if let Some(last) = app.tasks.items.last_mut() {
+            last.is_complete = tasks.len() > 1;
+        }
+        app.tasks.next(clock.now());
+        clock.advance(10 * MINUTE);
+        (app, clock)
+    }
+
+    fn render(app: &mut App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| ui(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut screen = String::new();
+        for row in buffer.content.chunks(usize::from(buffer.area.width)) {
+            let line: String = row.iter().map(|cell| cell.symbol.as_str()).collect();
+            screen.push_str(line.trim_end());
+            screen.push('\n');
+        }
+        screen
+    }
+
+    /// Compare `screen` with the snapshot called `name`, or write it as the snapshot when
+    /// `UPDATE_SNAPSHOTS` is set.
+    fn assert_snapshot(name: &str, screen: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/snapshots")
+            .join(format!("ui__{name}.txt"));
+        if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, screen).unwrap();
+            return;
+        }
+        let Ok(snapshot) = fs::read_to_string(&path) else {
+            panic!(
+                "missing snapshot {}, set UPDATE_SNAPSHOTS=1 to write it\ngot:\n{screen}",
+                path.display()
+            );
+        };
+        assert!(
+            snapshot == screen,
+            "{name} doesn't match {}, set UPDATE_SNAPSHOTS=1 to update it\n\
+             expected:\n{snapshot}\ngot:\n{screen}",
+            path.display()
+        );
+    }
+
+    const TASKS: &[&str] = &["Write report ~2", "Review pull requests", "Plan the week"];
+
+    #[test]
+    fn timer() {
+        let (mut app, _) = app(TASKS, "timer");
+        assert_snapshot("timer", &render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn tasks_tab() {
+        let (mut app, _) = app(TASKS, "tasks-tab");
+        app.tab = Tab::Tasks;
+        assert_snapshot("tasks_tab", &render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn stats_tab() {
+        let (mut app, _) = app(TASKS, "stats-tab");
+        app.tab = Tab::Stats;
+        assert_snapshot("stats_tab", &render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn history_tab() {
+        let (mut app, _) = app(TASKS, "history-tab");
+        app.tab = Tab::History;
+        assert_snapshot("history_tab", &render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn help() {
+        let (mut app, _) = app(TASKS, "help");
+        app.show_help = true;
+        assert_snapshot("help", &render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn adding_a_task() {
+        let (mut app, _) = app(TASKS, "adding-a-task");
+        for key in [KeyCode::Char('a'), KeyCode::Char('R'), KeyCode::Char('e')] {
+            app.handle_key(key);
+        }
+        app.handle_key(KeyCode::Backspace);
+        for c in "ead docs ~2".chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+        assert_snapshot("adding_a_task", &render(&mut app, 80, 24));
+
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.new_task, None);
+        let task = app.tasks.items.last().unwrap();
+        assert_eq!(task.name, "Read docs");
+        assert_eq!(task.estimate, Some(2));
+    }
+
+    #[test]
+    fn quit_dialog() {
+        let (mut app, _) = app(TASKS, "quit-dialog");
+        app.confirm_quit = true;
+        assert_snapshot("quit_dialog", &render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn taking_a_break() {
+        let (mut app, clock) = app(TASKS, "taking-a-break");
+        clock.advance(15 * MINUTE + TICK_RATE);
+        app.on_tick();
+        clock.advance(MINUTE);
+        assert_snapshot("taking_a_break", &render(&mut app, 80, 24));
+        let _ = fs::remove_file(history_path("taking-a-break"));
+    }
+
+    #[test]
+    fn stats_after_a_pomodoro() {
+        let (mut app, clock) = app(TASKS, "stats-after-a-pomodoro");
+        clock.advance(15 * MINUTE + TICK_RATE);
+        app.on_tick();
+        app.tab = Tab::Stats;
+        assert_snapshot("stats_after_a_pomodoro", &render(&mut app, 80, 24));
+        let _ = fs::remove_file(history_path("stats-after-a-pomodoro"));
+    }
+
+    #[test]
+    fn no_tasks() {
+        let (mut app, _) = app(&[], "no-tasks");
+        assert_snapshot("no_tasks", &render(&mut app, 80, 24));
+    }
+
+    #[test]
+    fn compact() {
+        let (mut app, _) = app(TASKS, "compact");
+        assert_snapshot("compact", &render(&mut app, 80, 3));
+    }
+
+    #[test]
+    fn compact_task_input() {
+        let (mut app, _) = app(&[], "compact-task-input");
+        assert_snapshot("compact_no_tasks", &render(&mut app, 80, 3));
+        app.handle_key(KeyCode::Char('a'));
+        for c in "Read docs".chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+        assert_snapshot("compact_task_input", &render(&mut app, 80, 3));
+    }
+
+    #[test]
+    fn compact_quit_question() {
+        let (mut app, _) = app(TASKS, "compact-quit-question");
+        app.handle_key(KeyCode::Char('q'));
+        assert!(app.confirm_quit);
+        assert_snapshot("compact_quit_question", &render(&mut app, 80, 3));
+    }
+}
@@ -0,0 +1,61 @@
+//! Posting period transitions to user configured URLs.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::thread;
+
+use crate::timer::Transition;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Transitions to post, all of them when empty
+    #[serde(default)]
+    pub events: Vec<Transition>,
+    /// JSON body to send, `{event}` and `{task}` in its strings are replaced. Defaults to an
+    /// object with the event, task and timestamp.
+    #[serde(default)]
+    pub payload: Option<Value>,
+}
+
+impl Webhook {
+    fn wants(&self, transition: Transition) -> bool {
+        self.events.is_empty() || self.events.contains(&transition)
+    }
+
+    fn body(&self, transition: Transition, task: Option<&str>) -> Value {
+        match &self.payload {
+            Some(payload) => fill_in(payload, transition.name(), task.unwrap_or_default()),
+            None => json!({
+                "event": transition,
+                "task": task,
+                "timestamp": chrono::Utc::now(),
+            }),
+        }
+    }
+}
+
+/// Send the transition to every webhook interested in it, without waiting for the responses.
+pub fn post(webhooks: &[Webhook], transition: Transition, task: Option<&str>) {
+    for webhook in webhooks.iter().filter(|webhook| webhook.wants(transition)) {
+        let url = webhook.url.clone();
+        let body = webhook.body(transition, task);
+        thread::spawn(move || {
+            // A receiver that's down just misses this transition, nothing is queued for it
+            let _ = ureq::post(&url).send_json(body);
+        });
+    }
+}
+
+fn fill_in(template: &Value, event: &str, task: &str) -> Value {
+    match template {
+        Value::String(s) => Value::String(s.replace("{event}", event).replace("{task}", task)),
+        Value::Array(values) => values.iter().map(|v| fill_in(v, event, task)).collect(),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), fill_in(v, event, task)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
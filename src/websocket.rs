@@ -0,0 +1,138 @@
+//! Pushing events to clients of the HTTP API over a WebSocket, so overlays and dashboards update
+//! without polling. Only what's needed to send text frames is implemented: whatever the client
+//! sends is read and dropped, and closing its end ends the connection.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// Appended to the client's key to show the server speaks WebSocket.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The WebSocket clients, which every event is sent to.
+#[derive(Clone, Default)]
+pub struct Clients(Arc<Mutex<Vec<Sender<String>>>>);
+
+impl Clients {
+    /// Send `event` to every client, forgetting those that went away.
+    pub fn broadcast(&self, event: &str) {
+        if let Ok(mut clients) = self.0.lock() {
+            clients.retain(|client| client.send(event.to_string()).is_ok());
+        }
+    }
+
+    fn subscribe(&self) -> Receiver<String> {
+        let (sender, receiver) = mpsc::channel();
+        if let Ok(mut clients) = self.0.lock() {
+            clients.push(sender);
+        }
+        receiver
+    }
+}
+
+/// Finish the handshake for a client that asked to upgrade with `key`, send it `first`, then
+/// every event until it goes away.
+pub fn serve(mut stream: TcpStream, key: &str, first: String, clients: &Clients) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept(key)
+    )?;
+    stream.set_read_timeout(None)?;
+
+    let mut reader = stream.try_clone()?;
+    thread::spawn(move || {
+        // Pings and messages aren't answered, only the end of the connection matters
+        let mut buf = [0; 1024];
+        while matches!(reader.read(&mut buf), Ok(n) if n > 0) {}
+        let _ = reader.shutdown(Shutdown::Both);
+    });
+
+    let events = clients.subscribe();
+    write_text(&mut stream, &first)?;
+    for event in events {
+        write_text(&mut stream, &event)?;
+    }
+    Ok(())
+}
+
+/// The `Sec-WebSocket-Accept` answer to a `Sec-WebSocket-Key`.
+fn accept(key: &str) -> String {
+    STANDARD.encode(sha1(format!("{}{GUID}", key.trim()).as_bytes()))
+}
+
+/// A final, unmasked text frame.
+fn write_text(stream: &mut TcpStream, text: &str) -> io::Result<()> {
+    let mut frame = vec![0x81];
+    let length = text.len();
+    if length < 126 {
+        frame.push(length as u8);
+    } else if let Ok(length) = u16::try_from(length) {
+        frame.push(126);
+        frame.extend(length.to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend((length as u64).to_be_bytes());
+    }
+    frame.extend(text.as_bytes());
+    stream.write_all(&frame)
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(x);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, h) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&h.to_be_bytes());
+    }
+    digest
+}